@@ -0,0 +1,77 @@
+use egui::{Align2, Hyperlink, RichText, Ui};
+
+use crate::panel::CornerPanels;
+
+/// A piece of attribution text, optionally linking to the provider's terms or copyright page.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Attribution {
+    pub text: String,
+    pub url: Option<String>,
+}
+
+impl Attribution {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            url: None,
+        }
+    }
+
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+}
+
+/// Merges attributions, dropping duplicates while keeping the order in which they first appear.
+pub(crate) fn merge_attributions(
+    attributions: impl IntoIterator<Item = Attribution>,
+) -> Vec<Attribution> {
+    let mut merged: Vec<Attribution> = Vec::new();
+    for a in attributions {
+        if !merged.contains(&a) {
+            merged.push(a);
+        }
+    }
+    merged
+}
+
+/// Draws the attribution box into the `anchor` corner of the map.
+pub(crate) fn show_attribution(
+    ui: &mut Ui,
+    panels: &mut CornerPanels,
+    anchor: Align2,
+    attributions: &[Attribution],
+) {
+    if attributions.is_empty() {
+        return;
+    }
+
+    let max_width = panels.map_rect().width();
+    panels.show(
+        ui,
+        anchor,
+        |frame| frame.inner_margin(2.0),
+        |ui| {
+            ui.set_max_width(max_width);
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.horizontal_wrapped(|ui| {
+                for (i, a) in attributions.iter().enumerate() {
+                    if i > 0 {
+                        ui.label(RichText::new(" | ").small());
+                    }
+
+                    let text = RichText::new(&a.text).small();
+                    match &a.url {
+                        Some(url) => {
+                            ui.add(Hyperlink::from_label_and_url(text, url).open_in_new_tab(true));
+                        }
+                        None => {
+                            ui.label(text);
+                        }
+                    }
+                }
+            });
+        },
+    );
+}
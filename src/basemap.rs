@@ -0,0 +1,296 @@
+use crate::{Attribution, TileId, TileUrlProvider};
+
+/// Static metadata for an entry of the [`Basemap`] catalogue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BasemapInfo {
+    pub name: &'static str,
+
+    /// URL template with `{s}`, `{z}`, `{x}`, `{y}` and `{api_key}` placeholders
+    pub url_template: &'static str,
+    /// values substituted for `{s}`, empty if the template has no subdomain
+    pub subdomains: &'static [&'static str],
+
+    pub max_zoom: u8,
+    pub tile_size: u32,
+
+    /// attribution text the provider requires to be shown alongside the map
+    pub attribution: &'static str,
+    /// page the attribution should link to
+    pub attribution_url: &'static str,
+
+    pub requires_api_key: bool,
+}
+
+/// Commonly used raster basemaps.
+///
+/// ```no_run
+/// use emap::{Basemap, EMap, TileLayer};
+///
+/// fn map_ui(ui: &mut egui::Ui, provider: &emap::BasemapTileUrlProvider) {
+///     EMap::new("map").base_layer(TileLayer::basemap(provider)).show(ui);
+/// }
+///
+/// let provider = Basemap::ThunderforestCycle.with_api_key("my-key");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Basemap {
+    OsmStandard,
+    OpenTopoMap,
+    CyclOsm,
+    EsriWorldImagery,
+    StadiaAlidadeSmooth,
+    StadiaAlidadeSmoothDark,
+    StadiaOutdoors,
+    ThunderforestCycle,
+    ThunderforestTransport,
+    ThunderforestOutdoors,
+    ThunderforestLandscape,
+    CartoPositron,
+    CartoDarkMatter,
+    CartoVoyager,
+}
+
+const OSM_ATTRIBUTION: &str = "© OpenStreetMap contributors";
+const OSM_COPYRIGHT: &str = "https://www.openstreetmap.org/copyright";
+
+const STADIA_ATTRIBUTION: &str = "© Stadia Maps © OpenMapTiles © OpenStreetMap contributors";
+const STADIA_COPYRIGHT: &str = "https://stadiamaps.com/attribution";
+
+const THUNDERFOREST_ATTRIBUTION: &str = "Maps © Thunderforest, Data © OpenStreetMap contributors";
+const THUNDERFOREST_COPYRIGHT: &str = "https://www.thunderforest.com/terms/";
+
+const CARTO_ATTRIBUTION: &str = "© OpenStreetMap contributors © CARTO";
+const CARTO_COPYRIGHT: &str = "https://carto.com/attributions";
+const CARTO_SUBDOMAINS: &[&str] = &["a", "b", "c", "d"];
+
+impl Basemap {
+    pub const ALL: &'static [Basemap] = &[
+        Basemap::OsmStandard,
+        Basemap::OpenTopoMap,
+        Basemap::CyclOsm,
+        Basemap::EsriWorldImagery,
+        Basemap::StadiaAlidadeSmooth,
+        Basemap::StadiaAlidadeSmoothDark,
+        Basemap::StadiaOutdoors,
+        Basemap::ThunderforestCycle,
+        Basemap::ThunderforestTransport,
+        Basemap::ThunderforestOutdoors,
+        Basemap::ThunderforestLandscape,
+        Basemap::CartoPositron,
+        Basemap::CartoDarkMatter,
+        Basemap::CartoVoyager,
+    ];
+
+    pub fn info(&self) -> BasemapInfo {
+        match self {
+            Basemap::OsmStandard => BasemapInfo {
+                name: "OpenStreetMap",
+                url_template: "https://tile.openstreetmap.org/{z}/{x}/{y}.png",
+                subdomains: &[],
+                max_zoom: 19,
+                tile_size: 256,
+                attribution: OSM_ATTRIBUTION,
+                attribution_url: OSM_COPYRIGHT,
+                requires_api_key: false,
+            },
+            Basemap::OpenTopoMap => BasemapInfo {
+                name: "OpenTopoMap",
+                url_template: "https://{s}.tile.opentopomap.org/{z}/{x}/{y}.png",
+                subdomains: &["a", "b", "c"],
+                max_zoom: 17,
+                tile_size: 256,
+                attribution: "Map data: © OpenStreetMap contributors, SRTM | Map style: © OpenTopoMap (CC-BY-SA)",
+                attribution_url: "https://opentopomap.org/about",
+                requires_api_key: false,
+            },
+            Basemap::CyclOsm => BasemapInfo {
+                name: "CyclOSM",
+                url_template: "https://{s}.tile-cyclosm.openstreetmap.fr/cyclosm/{z}/{x}/{y}.png",
+                subdomains: &["a", "b", "c"],
+                max_zoom: 20,
+                tile_size: 256,
+                attribution: "CyclOSM | Map data: © OpenStreetMap contributors",
+                attribution_url: "https://www.cyclosm.org",
+                requires_api_key: false,
+            },
+            Basemap::EsriWorldImagery => BasemapInfo {
+                name: "Esri World Imagery",
+                url_template: "https://server.arcgisonline.com/ArcGIS/rest/services/World_Imagery/MapServer/tile/{z}/{y}/{x}",
+                subdomains: &[],
+                max_zoom: 19,
+                tile_size: 256,
+                attribution: "Tiles © Esri — Source: Esri, i-cubed, USDA, USGS, AEX, GeoEye, Getmapping, Aerogrid, IGN, IGP, UPR-EGP, and the GIS User Community",
+                attribution_url: "https://www.esri.com/en-us/legal/terms/full-master-agreement",
+                requires_api_key: false,
+            },
+            Basemap::StadiaAlidadeSmooth => BasemapInfo {
+                name: "Stadia Alidade Smooth",
+                url_template: "https://tiles.stadiamaps.com/tiles/alidade_smooth/{z}/{x}/{y}.png?api_key={api_key}",
+                subdomains: &[],
+                max_zoom: 20,
+                tile_size: 256,
+                attribution: STADIA_ATTRIBUTION,
+                attribution_url: STADIA_COPYRIGHT,
+                requires_api_key: true,
+            },
+            Basemap::StadiaAlidadeSmoothDark => BasemapInfo {
+                name: "Stadia Alidade Smooth Dark",
+                url_template: "https://tiles.stadiamaps.com/tiles/alidade_smooth_dark/{z}/{x}/{y}.png?api_key={api_key}",
+                subdomains: &[],
+                max_zoom: 20,
+                tile_size: 256,
+                attribution: STADIA_ATTRIBUTION,
+                attribution_url: STADIA_COPYRIGHT,
+                requires_api_key: true,
+            },
+            Basemap::StadiaOutdoors => BasemapInfo {
+                name: "Stadia Outdoors",
+                url_template: "https://tiles.stadiamaps.com/tiles/outdoors/{z}/{x}/{y}.png?api_key={api_key}",
+                subdomains: &[],
+                max_zoom: 20,
+                tile_size: 256,
+                attribution: STADIA_ATTRIBUTION,
+                attribution_url: STADIA_COPYRIGHT,
+                requires_api_key: true,
+            },
+            Basemap::ThunderforestCycle => BasemapInfo {
+                name: "Thunderforest OpenCycleMap",
+                url_template: "https://tile.thunderforest.com/cycle/{z}/{x}/{y}.png?apikey={api_key}",
+                subdomains: &[],
+                max_zoom: 22,
+                tile_size: 256,
+                attribution: THUNDERFOREST_ATTRIBUTION,
+                attribution_url: THUNDERFOREST_COPYRIGHT,
+                requires_api_key: true,
+            },
+            Basemap::ThunderforestTransport => BasemapInfo {
+                name: "Thunderforest Transport",
+                url_template: "https://tile.thunderforest.com/transport/{z}/{x}/{y}.png?apikey={api_key}",
+                subdomains: &[],
+                max_zoom: 22,
+                tile_size: 256,
+                attribution: THUNDERFOREST_ATTRIBUTION,
+                attribution_url: THUNDERFOREST_COPYRIGHT,
+                requires_api_key: true,
+            },
+            Basemap::ThunderforestOutdoors => BasemapInfo {
+                name: "Thunderforest Outdoors",
+                url_template: "https://tile.thunderforest.com/outdoors/{z}/{x}/{y}.png?apikey={api_key}",
+                subdomains: &[],
+                max_zoom: 22,
+                tile_size: 256,
+                attribution: THUNDERFOREST_ATTRIBUTION,
+                attribution_url: THUNDERFOREST_COPYRIGHT,
+                requires_api_key: true,
+            },
+            Basemap::ThunderforestLandscape => BasemapInfo {
+                name: "Thunderforest Landscape",
+                url_template: "https://tile.thunderforest.com/landscape/{z}/{x}/{y}.png?apikey={api_key}",
+                subdomains: &[],
+                max_zoom: 22,
+                tile_size: 256,
+                attribution: THUNDERFOREST_ATTRIBUTION,
+                attribution_url: THUNDERFOREST_COPYRIGHT,
+                requires_api_key: true,
+            },
+            Basemap::CartoPositron => BasemapInfo {
+                name: "CARTO Positron",
+                url_template: "https://{s}.basemaps.cartocdn.com/light_all/{z}/{x}/{y}.png",
+                subdomains: CARTO_SUBDOMAINS,
+                max_zoom: 20,
+                tile_size: 256,
+                attribution: CARTO_ATTRIBUTION,
+                attribution_url: CARTO_COPYRIGHT,
+                requires_api_key: false,
+            },
+            Basemap::CartoDarkMatter => BasemapInfo {
+                name: "CARTO Dark Matter",
+                url_template: "https://{s}.basemaps.cartocdn.com/dark_all/{z}/{x}/{y}.png",
+                subdomains: CARTO_SUBDOMAINS,
+                max_zoom: 20,
+                tile_size: 256,
+                attribution: CARTO_ATTRIBUTION,
+                attribution_url: CARTO_COPYRIGHT,
+                requires_api_key: false,
+            },
+            Basemap::CartoVoyager => BasemapInfo {
+                name: "CARTO Voyager",
+                url_template: "https://{s}.basemaps.cartocdn.com/rastertiles/voyager/{z}/{x}/{y}.png",
+                subdomains: CARTO_SUBDOMAINS,
+                max_zoom: 20,
+                tile_size: 256,
+                attribution: CARTO_ATTRIBUTION,
+                attribution_url: CARTO_COPYRIGHT,
+                requires_api_key: false,
+            },
+        }
+    }
+
+    /// Provider for basemaps that don't need an API key, fails for those that do.
+    pub fn provider(self) -> Result<BasemapTileUrlProvider, ApiKeyRequired> {
+        if self.info().requires_api_key {
+            return Err(ApiKeyRequired(self));
+        }
+
+        Ok(BasemapTileUrlProvider {
+            basemap: self,
+            api_key: None,
+        })
+    }
+
+    pub fn with_api_key(self, api_key: &str) -> BasemapTileUrlProvider {
+        BasemapTileUrlProvider {
+            basemap: self,
+            api_key: Some(api_key.to_string()),
+        }
+    }
+}
+
+/// [`Basemap::provider`] was called for a basemap that can only be used with an API key, see
+/// [`Basemap::with_api_key`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApiKeyRequired(pub Basemap);
+
+impl std::fmt::Display for ApiKeyRequired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} requires an API key", self.0.info().name)
+    }
+}
+
+impl std::error::Error for ApiKeyRequired {}
+
+pub struct BasemapTileUrlProvider {
+    basemap: Basemap,
+    api_key: Option<String>,
+}
+
+impl BasemapTileUrlProvider {
+    pub fn basemap(&self) -> Basemap {
+        self.basemap
+    }
+}
+
+impl TileUrlProvider for BasemapTileUrlProvider {
+    fn url(&self, tile_id: TileId) -> String {
+        let info = self.basemap.info();
+
+        let subdomain = if info.subdomains.is_empty() {
+            ""
+        } else {
+            let i = (tile_id.x + tile_id.y).unsigned_abs() as usize % info.subdomains.len();
+            info.subdomains[i]
+        };
+
+        info.url_template
+            .replace("{s}", subdomain)
+            .replace("{z}", &tile_id.z.to_string())
+            .replace("{x}", &tile_id.x.to_string())
+            .replace("{y}", &tile_id.y.to_string())
+            .replace("{api_key}", self.api_key.as_deref().unwrap_or_default())
+    }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        let info = self.basemap.info();
+        vec![Attribution::new(info.attribution).with_url(info.attribution_url)]
+    }
+}
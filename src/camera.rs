@@ -0,0 +1,230 @@
+use egui::Vec2;
+use geo::Point;
+
+use crate::{normalized_mercator, reverse_normalized_mercator};
+
+/// The zoom range the map can be zoomed to, with the mouse wheel or through a [`Camera`].
+pub(crate) const MIN_ZOOM: f64 = 0.75;
+pub(crate) const MAX_ZOOM: f64 = 20.1;
+
+/// The highest zoom fitting bounds zooms to, a single point would otherwise zoom in all the way.
+const MAX_FIT_ZOOM: f64 = 18.0;
+
+/// Where the map looks: its center as longitude/latitude and a fractional zoom, where zoom `z`
+/// shows the world `tile_size * 2^z` screen points wide.
+///
+/// Read it from [`crate::EMapResponse::camera`] or [`crate::EMap::camera`] and move the map
+/// with [`crate::EMap::set_camera`], e.g. for a side panel that shows and edits the view:
+///
+/// ```no_run
+/// use emap::EMap;
+///
+/// fn map_ui(ui: &mut egui::Ui) {
+///     let map = EMap::new("map");
+///     let mut camera = map.camera(ui.ctx());
+///
+///     ui.horizontal(|ui| {
+///         ui.add(egui::DragValue::new(&mut camera.center.0.y).prefix("lat ").speed(0.01));
+///         ui.add(egui::DragValue::new(&mut camera.center.0.x).prefix("lon ").speed(0.01));
+///         ui.add(egui::DragValue::new(&mut camera.zoom).prefix("zoom ").speed(0.05));
+///     });
+///
+///     let response = map.set_camera(ui.ctx(), camera).show(ui);
+///     let bounds = response.visible_bounds();
+///     ui.label(format!("{:.3?} to {:.3?}", bounds.min(), bounds.max()));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    /// Longitude as `x` and latitude as `y`.
+    pub center: Point<f64>,
+    pub zoom: f64,
+}
+
+impl Camera {
+    pub fn new(center: Point<f64>, zoom: f64) -> Self {
+        Self { center, zoom }
+    }
+
+    /// The camera that centers the longitude/latitude `bounds` and zooms in as far as they still
+    /// fit into a map widget of `size` screen points, leaving `padding` screen points free on
+    /// every side.
+    pub fn fit_bounds(bounds: geo::Rect<f64>, size: Vec2, padding: f32, tile_size: f64) -> Self {
+        let a = normalized_mercator(bounds.min().into());
+        let b = normalized_mercator(bounds.max().into());
+        let center = Point::new((a.x() + b.x()) / 2.0, (a.y() + b.y()) / 2.0);
+
+        let w = (size.x - 2.0 * padding).max(1.0) as f64;
+        let h = (size.y - 2.0 * padding).max(1.0) as f64;
+        let dx = (a.x() - b.x()).abs();
+        let dy = (a.y() - b.y()).abs();
+        let zoom = ((w / dx).min(h / dy) / tile_size).log2();
+
+        Self::new(
+            reverse_normalized_mercator(center),
+            zoom.clamp(MIN_ZOOM, MAX_FIT_ZOOM),
+        )
+    }
+
+    pub fn lat(&self) -> f64 {
+        self.center.y()
+    }
+
+    pub fn lon(&self) -> f64 {
+        self.center.x()
+    }
+}
+
+/// How the progress of a [`Transition`] speeds up and slows down over its duration.
+#[derive(Debug, Clone, Copy)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// Maps the elapsed fraction of the duration to the progress, both from `0.0` to `1.0`.
+    Custom(fn(f64) -> f64),
+}
+
+impl Easing {
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::Custom(f) => f(t),
+        }
+    }
+}
+
+/// The timing of an animated camera move, see [`crate::EMap::ease_to`] and
+/// [`crate::EMap::fly_to`]. Dragging or scrolling the map cancels it.
+#[derive(Debug, Clone, Copy)]
+pub struct Transition {
+    pub(crate) duration: f64,
+    pub(crate) easing: Easing,
+    pub(crate) curve: f64,
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self {
+            duration: 1.0,
+            easing: Easing::EaseInOut,
+            curve: 1.42,
+        }
+    }
+}
+
+impl Transition {
+    /// A transition taking `duration` seconds.
+    pub fn new(duration: f64) -> Self {
+        Self::default().duration(duration)
+    }
+
+    pub fn duration(mut self, seconds: f64) -> Self {
+        self.duration = seconds.max(0.0);
+        self
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// How far [`crate::EMap::fly_to`] zooms out on the way, `1.42` by default. Larger values
+    /// zoom out further, values close to `0.0` hardly at all.
+    pub fn curve(mut self, rho: f64) -> Self {
+        self.curve = rho.max(0.01);
+        self
+    }
+}
+
+/// A view in normalized mercator coordinates, as the map state keeps it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct View {
+    pub x: f64,
+    pub y: f64,
+    pub zoom: f64,
+}
+
+/// A running camera transition.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Animation {
+    pub from: View,
+    pub to: View,
+    /// The `egui` input time the transition started at, in seconds.
+    pub start: f64,
+    pub transition: Transition,
+    /// Whether to zoom out and in again along the way instead of moving straight.
+    pub fly: bool,
+}
+
+impl Animation {
+    /// The view at `time`, for a map whose larger side is `size` screen points, and whether the
+    /// transition is over.
+    pub fn at(&self, time: f64, size: f64, tile_size: f64) -> (View, bool) {
+        let elapsed = (time - self.start) / self.transition.duration;
+        // a zero duration gives NaN or infinity
+        if elapsed.is_nan() || elapsed >= 1.0 {
+            return (self.to, true);
+        }
+        let t = self.transition.easing.apply(elapsed);
+
+        let (from, to) = (self.from, self.to);
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        if !self.fly {
+            let view = View {
+                x: lerp(from.x, to.x, t),
+                y: lerp(from.y, to.y, t),
+                zoom: lerp(from.zoom, to.zoom, t),
+            };
+            return (view, false);
+        }
+
+        // van Wijk and Nuij, "Smooth and efficient zooming and panning", with the widths of the
+        // views and the distance between them in screen points at the start zoom
+        let rho = self.transition.curve;
+        let rho2 = rho * rho;
+        let scale = tile_size * 2.0f64.powf(from.zoom);
+        let w0 = size;
+        let w1 = size / 2.0f64.powf(to.zoom - from.zoom);
+        let u1 = (to.x - from.x).hypot(to.y - from.y) * scale;
+
+        let r = |i: bool| {
+            let (sign, w) = if i { (-1.0, w1) } else { (1.0, w0) };
+            let b = (w1 * w1 - w0 * w0 + sign * rho2 * rho2 * u1 * u1) / (2.0 * w * rho2 * u1);
+            ((b * b + 1.0).sqrt() - b).ln()
+        };
+        let r0 = r(false);
+        let length = (r(true) - r0) / rho;
+
+        let (width, along) = if u1 < 1e-6 || !length.is_finite() {
+            // hardly any panning, just zoom
+            let k = if w1 < w0 { -1.0 } else { 1.0 };
+            let length = (w1 / w0).ln().abs() / rho;
+            let s = t * length;
+            ((k * rho * s).exp(), t)
+        } else {
+            let s = t * length;
+            let width = r0.cosh() / (r0 + rho * s).cosh();
+            let along = w0 * (r0.cosh() * (r0 + rho * s).tanh() - r0.sinh()) / rho2 / u1;
+            (width, along)
+        };
+
+        let view = View {
+            x: lerp(from.x, to.x, along),
+            y: lerp(from.y, to.y, along),
+            zoom: from.zoom - width.log2(),
+        };
+        (view, false)
+    }
+}
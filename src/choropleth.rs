@@ -0,0 +1,313 @@
+use egui::{Align, Align2, Color32, Layout, Mesh, RichText, Sense, Stroke, Ui, Vec2};
+use geo::MultiPolygon;
+
+use crate::{Interpolate, panel::CornerPanels};
+
+/// Maps numbers to colors, e.g. to color polygons by a property in the style function of
+/// [`crate::EMap::geojson`] or in a [`Choropleth`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorScale {
+    /// Colors evenly spread from `min` to `max` and interpolated in between. Values outside are
+    /// clamped.
+    Linear {
+        min: f64,
+        max: f64,
+        colors: Vec<Color32>,
+    },
+    /// Values below `breaks[0]` get the first color, values from `breaks[i - 1]` below
+    /// `breaks[i]` the color `i`, values from the last break on the last color. `min` and `max`
+    /// are only shown in the legend.
+    Classes {
+        min: f64,
+        max: f64,
+        breaks: Vec<f64>,
+        colors: Vec<Color32>,
+    },
+    /// A color for each of a few distinct values, with a name for the legend.
+    Categorical {
+        categories: Vec<(f64, String, Color32)>,
+    },
+}
+
+impl ColorScale {
+    pub fn linear(min: f64, max: f64, colors: &[Color32]) -> Self {
+        Self::Linear {
+            min,
+            max,
+            colors: colors.to_vec(),
+        }
+    }
+
+    /// Classes holding about the same number of `values` each, one for every color.
+    pub fn quantile(values: impl IntoIterator<Item = f64>, colors: &[Color32]) -> Self {
+        let mut values = values
+            .into_iter()
+            .filter(|v| v.is_finite())
+            .collect::<Vec<_>>();
+        values.sort_by(f64::total_cmp);
+
+        let n = values.len();
+        let breaks = (1..colors.len())
+            .filter_map(|i| values.get(i * n / colors.len()).copied())
+            .collect();
+
+        Self::Classes {
+            min: values.first().copied().unwrap_or(f64::NAN),
+            max: values.last().copied().unwrap_or(f64::NAN),
+            breaks,
+            colors: colors.to_vec(),
+        }
+    }
+
+    pub fn categorical<S: Into<String>>(
+        categories: impl IntoIterator<Item = (f64, S, Color32)>,
+    ) -> Self {
+        Self::Categorical {
+            categories: categories
+                .into_iter()
+                .map(|(value, name, color)| (value, name.into(), color))
+                .collect(),
+        }
+    }
+
+    /// The color of `value`, `None` for NaN, unknown categories and scales without colors.
+    pub fn color(&self, value: f64) -> Option<Color32> {
+        if value.is_nan() {
+            return None;
+        }
+
+        match self {
+            Self::Linear { min, max, colors } => {
+                let last = colors.len().checked_sub(1)?;
+                let t = ((value - min) / (max - min)).clamp(0.0, 1.0);
+                let t = if t.is_nan() { 0.0 } else { t * last as f64 };
+                let i = (t.floor() as usize).min(last.saturating_sub(1));
+                Some(match colors.get(i + 1) {
+                    Some(next) => colors[i].interpolate(next, t - i as f64),
+                    None => colors[i],
+                })
+            }
+            Self::Classes { breaks, colors, .. } => {
+                let class = breaks.iter().take_while(|b| value >= **b).count();
+                colors.get(class.min(colors.len().checked_sub(1)?)).copied()
+            }
+            Self::Categorical { categories } => categories
+                .iter()
+                .find(|(v, _, _)| *v == value)
+                .map(|(_, _, color)| *color),
+        }
+    }
+}
+
+/// A legend box explaining a [`ColorScale`], see [`crate::EMap::legend`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Legend {
+    title: String,
+    scale: ColorScale,
+    anchor: Align2,
+}
+
+impl Legend {
+    pub fn new(title: impl Into<String>, scale: ColorScale) -> Self {
+        Self {
+            title: title.into(),
+            scale,
+            anchor: Align2::LEFT_BOTTOM,
+        }
+    }
+
+    /// The corner of the map the legend is placed in, bottom left by default.
+    pub fn anchor(mut self, anchor: Align2) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Draws the legend box into its corner of the map.
+    pub(crate) fn show(&self, ui: &mut Ui, panels: &mut CornerPanels) {
+        panels.show(
+            ui,
+            self.anchor,
+            |frame| frame.inner_margin(6.0).corner_radius(4.0),
+            |ui| {
+                ui.with_layout(Layout::top_down(Align::Min), |ui| {
+                    ui.spacing_mut().item_spacing.y = 2.0;
+                    if !self.title.is_empty() {
+                        ui.label(RichText::new(&self.title).strong());
+                    }
+                    self.show_scale(ui);
+                });
+            },
+        );
+    }
+
+    fn show_scale(&self, ui: &mut Ui) {
+        match &self.scale {
+            ColorScale::Linear { min, max, colors } => {
+                let (rect, _) = ui.allocate_exact_size(Vec2::new(140.0, 12.0), Sense::hover());
+                if let [color] = colors.as_slice() {
+                    ui.painter().rect_filled(rect, 0.0, *color);
+                }
+                let mut mesh = Mesh::default();
+                for (i, color) in colors.iter().enumerate() {
+                    let t = i as f32 / (colors.len() - 1).max(1) as f32;
+                    let x = rect.left() + t * rect.width();
+                    mesh.colored_vertex(egui::pos2(x, rect.top()), *color);
+                    mesh.colored_vertex(egui::pos2(x, rect.bottom()), *color);
+                    if i > 0 {
+                        let v = 2 * i as u32;
+                        mesh.add_triangle(v - 2, v - 1, v);
+                        mesh.add_triangle(v - 1, v + 1, v);
+                    }
+                }
+                ui.painter().add(mesh);
+                ui.painter().rect_stroke(
+                    rect,
+                    0.0,
+                    ui.visuals().window_stroke,
+                    egui::StrokeKind::Inside,
+                );
+
+                ui.allocate_ui_with_layout(
+                    Vec2::new(rect.width(), 0.0),
+                    Layout::left_to_right(Align::Min),
+                    |ui| {
+                        ui.set_width(rect.width());
+                        ui.small(format_value(*min));
+                        ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
+                            ui.small(format_value(*max));
+                        });
+                    },
+                );
+            }
+            ColorScale::Classes {
+                min,
+                max,
+                breaks,
+                colors,
+            } => {
+                for (i, color) in colors.iter().enumerate() {
+                    let from = if i == 0 { *min } else { breaks[i - 1] };
+                    let to = breaks.get(i).copied().unwrap_or(*max);
+                    let text = format!("{} – {}", format_value(from), format_value(to));
+                    swatch(ui, *color, text);
+                    if i >= breaks.len() {
+                        break;
+                    }
+                }
+            }
+            ColorScale::Categorical { categories } => {
+                for (_, name, color) in categories {
+                    swatch(ui, *color, name.clone());
+                }
+            }
+        }
+    }
+}
+
+fn swatch(ui: &mut Ui, color: Color32, text: String) {
+    ui.horizontal(|ui| {
+        let (rect, _) = ui.allocate_exact_size(Vec2::new(14.0, 14.0), Sense::hover());
+        ui.painter().rect(
+            rect,
+            2.0,
+            color,
+            ui.visuals().window_stroke,
+            egui::StrokeKind::Inside,
+        );
+        ui.small(text);
+    });
+}
+
+/// A short, readable form of a legend value.
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "–".to_string()
+    } else if value.abs() >= 1000.0 || value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.2}")
+    }
+}
+
+/// Polygons colored by a value each, see [`crate::EMap::choropleth`].
+///
+/// ```no_run
+/// use egui::Color32;
+/// use emap::{Choropleth, ColorScale, EMap};
+///
+/// # let cantons: Vec<(geo::MultiPolygon<f64>, f64)> = Vec::new();
+/// let scale = ColorScale::quantile(
+///     cantons.iter().map(|(_, density)| *density),
+///     &[
+///         Color32::from_rgb(255, 255, 204),
+///         Color32::from_rgb(161, 218, 180),
+///         Color32::from_rgb(65, 182, 196),
+///         Color32::from_rgb(34, 94, 168),
+///     ],
+/// );
+/// let choropleth = Choropleth::new(scale)
+///     .regions(cantons)
+///     .legend("Inhabitants per km²");
+///
+/// fn density_ui(ui: &mut egui::Ui, choropleth: &Choropleth) {
+///     EMap::new("map").choropleth(choropleth).show(ui);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Choropleth {
+    pub(crate) regions: Vec<(MultiPolygon<f64>, f64)>,
+    pub(crate) scale: ColorScale,
+    pub(crate) stroke: Option<Stroke>,
+    pub(crate) no_data: Option<Color32>,
+    pub(crate) legend: Option<Legend>,
+}
+
+impl Choropleth {
+    pub fn new(scale: ColorScale) -> Self {
+        Self {
+            regions: Vec::new(),
+            scale,
+            stroke: Some(Stroke::new(1.0, Color32::WHITE)),
+            no_data: Some(Color32::from_gray(180).gamma_multiply(0.6)),
+            legend: None,
+        }
+    }
+
+    pub fn region(mut self, polygon: impl Into<MultiPolygon<f64>>, value: f64) -> Self {
+        self.regions.push((polygon.into(), value));
+        self
+    }
+
+    pub fn regions<P: Into<MultiPolygon<f64>>>(
+        mut self,
+        regions: impl IntoIterator<Item = (P, f64)>,
+    ) -> Self {
+        self.regions
+            .extend(regions.into_iter().map(|(p, v)| (p.into(), v)));
+        self
+    }
+
+    /// The outline of every region.
+    pub fn stroke(mut self, stroke: impl Into<Option<Stroke>>) -> Self {
+        self.stroke = stroke.into();
+        self
+    }
+
+    /// The fill of regions the scale has no color for, e.g. with a NaN value.
+    pub fn no_data(mut self, fill: impl Into<Option<Color32>>) -> Self {
+        self.no_data = fill.into();
+        self
+    }
+
+    /// Shows a legend of the scale in the bottom left corner of the map.
+    pub fn legend(mut self, title: impl Into<String>) -> Self {
+        self.legend = Some(Legend::new(title, self.scale.clone()));
+        self
+    }
+
+    /// Shows a legend of the scale in the `anchor` corner of the map.
+    pub fn legend_at(mut self, title: impl Into<String>, anchor: Align2) -> Self {
+        self.legend = Some(Legend::new(title, self.scale.clone()).anchor(anchor));
+        self
+    }
+}
@@ -0,0 +1,267 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use egui::{Align2, Color32, FontId, Painter, Pos2, Stroke};
+use geo::Point;
+
+use crate::{GeometryStyle, normalized_mercator, reverse_normalized_mercator};
+
+/// Many points that are grouped into numbered bubbles where they are too close to each other at
+/// the current zoom, see [`crate::EMap::clusters`]. Clicking a bubble zooms in on its points.
+///
+/// Clusters are formed hierarchically like in [supercluster](https://github.com/mapbox/supercluster):
+/// the points are merged with their neighbours within the cluster radius at the highest
+/// clustered zoom, those clusters again one zoom level lower and so on.
+///
+/// ```no_run
+/// use emap::{ClusterLayer, EMap};
+///
+/// fn pings_ui(ui: &mut egui::Ui, clusters: &ClusterLayer) {
+///     EMap::new("map").clusters(clusters).show(ui);
+/// }
+///
+/// # let pings: Vec<geo::Point<f64>> = Vec::new();
+/// let clusters = ClusterLayer::new(pings).radius(50.0);
+/// ```
+pub struct ClusterLayer {
+    /// Normalized mercator positions.
+    points: Vec<Point<f64>>,
+
+    radius: f32,
+    max_zoom: u8,
+    point_style: GeometryStyle,
+    bubble_color: Color32,
+    text_color: Color32,
+
+    /// The clusters of every zoom level up to `max_zoom`, for the tile size they were built for.
+    levels: Mutex<Option<(f64, Vec<Vec<Cluster>>)>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Cluster {
+    /// Normalized mercator position, the mean of its points.
+    pos: Point<f64>,
+    count: usize,
+    /// The normalized mercator extent of its points.
+    bounds: geo::Rect<f64>,
+}
+
+/// A bubble as painted, for handling clicks on it.
+pub(crate) struct Bubble {
+    pub center: Pos2,
+    pub radius: f32,
+    /// The extent of the clustered points in longitude/latitude.
+    pub bounds: geo::Rect<f64>,
+}
+
+impl ClusterLayer {
+    pub fn new(points: impl IntoIterator<Item = Point<f64>>) -> Self {
+        Self {
+            points: points.into_iter().map(normalized_mercator).collect(),
+
+            radius: 60.0,
+            max_zoom: 16,
+            point_style: GeometryStyle::new().fill(Color32::from_rgb(51, 136, 255)),
+            bubble_color: Color32::from_rgba_unmultiplied(51, 136, 255, 200),
+            text_color: Color32::WHITE,
+
+            levels: Mutex::new(None),
+        }
+    }
+
+    /// Points closer than `radius` screen points are clustered.
+    pub fn radius(mut self, radius: f32) -> Self {
+        self.radius = radius.max(1.0);
+        self
+    }
+
+    /// The highest zoom at which points are clustered, above it every point is shown.
+    pub fn max_zoom(mut self, zoom: u8) -> Self {
+        self.max_zoom = zoom.min(30);
+        self
+    }
+
+    /// How points that aren't part of a cluster are drawn.
+    pub fn point_style(mut self, style: impl Into<GeometryStyle>) -> Self {
+        self.point_style = style.into();
+        self
+    }
+
+    pub fn bubble(mut self, fill: Color32, text: Color32) -> Self {
+        self.bubble_color = fill;
+        self.text_color = text;
+        self
+    }
+
+    /// Paints the clusters of `zoom` that lie within the painter's clip rect.
+    pub(crate) fn paint(
+        &self,
+        painter: &Painter,
+        zoom: u8,
+        tile_size: f64,
+        to_screen: impl Fn(Point<f64>) -> Pos2,
+    ) -> Vec<Bubble> {
+        let mut levels = self.levels.lock().unwrap();
+        if levels.as_ref().is_none_or(|(size, _)| *size != tile_size) {
+            *levels = Some((tile_size, self.build_levels(tile_size)));
+        }
+        let (_, levels) = levels.as_ref().unwrap();
+
+        let visible = painter.clip_rect().expand(self.radius);
+        let mut bubbles = Vec::new();
+
+        let Some(clusters) = levels.get(zoom as usize) else {
+            for p in &self.points {
+                let pos = to_screen(*p);
+                if visible.contains(pos) {
+                    self.paint_point(painter, pos);
+                }
+            }
+            return bubbles;
+        };
+
+        for cluster in clusters {
+            let pos = to_screen(cluster.pos);
+            if !visible.contains(pos) {
+                continue;
+            }
+
+            if cluster.count == 1 {
+                self.paint_point(painter, pos);
+                continue;
+            }
+
+            let radius = 12.0 + 4.0 * (cluster.count as f32).log10();
+            painter.circle(
+                pos,
+                radius + 4.0,
+                self.bubble_color.gamma_multiply(0.4),
+                Stroke::NONE,
+            );
+            painter.circle_filled(pos, radius, self.bubble_color);
+            painter.text(
+                pos,
+                Align2::CENTER_CENTER,
+                cluster.count.to_string(),
+                FontId::proportional(12.0),
+                self.text_color,
+            );
+
+            let (min, max) = (cluster.bounds.min(), cluster.bounds.max());
+            bubbles.push(Bubble {
+                center: pos,
+                radius: radius + 4.0,
+                bounds: geo::Rect::new(
+                    reverse_normalized_mercator(min.into()),
+                    reverse_normalized_mercator(max.into()),
+                ),
+            });
+        }
+
+        bubbles
+    }
+
+    fn paint_point(&self, painter: &Painter, pos: Pos2) {
+        let style = &self.point_style;
+        if let Some(marker) = &style.marker {
+            marker.paint(painter, pos);
+            return;
+        }
+
+        let fill = style.fill.unwrap_or(Color32::TRANSPARENT);
+        let stroke = style.stroke.unwrap_or(Stroke::NONE);
+        painter.circle(pos, style.point_radius, fill, stroke);
+    }
+
+    fn build_levels(&self, tile_size: f64) -> Vec<Vec<Cluster>> {
+        let mut clusters = self
+            .points
+            .iter()
+            .map(|p| Cluster {
+                pos: *p,
+                count: 1,
+                bounds: geo::Rect::new(*p, *p),
+            })
+            .collect::<Vec<_>>();
+
+        let mut levels = vec![Vec::new(); self.max_zoom as usize + 1];
+        for zoom in (0..=self.max_zoom).rev() {
+            let radius = self.radius as f64 / (tile_size * 2.0f64.powi(zoom as i32));
+            clusters = merge(&clusters, radius);
+            levels[zoom as usize] = clusters.clone();
+        }
+
+        levels
+    }
+}
+
+/// Merges every cluster with its unmerged neighbours within `radius`.
+fn merge(clusters: &[Cluster], radius: f64) -> Vec<Cluster> {
+    let cell = |p: Point<f64>| {
+        (
+            (p.x() / radius).floor() as i64,
+            (p.y() / radius).floor() as i64,
+        )
+    };
+
+    let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, c) in clusters.iter().enumerate() {
+        grid.entry(cell(c.pos)).or_default().push(i);
+    }
+
+    let mut merged = vec![false; clusters.len()];
+    let mut result = Vec::new();
+    for (i, c) in clusters.iter().enumerate() {
+        if merged[i] {
+            continue;
+        }
+        merged[i] = true;
+
+        let mut sum = c.pos * c.count as f64;
+        let mut count = c.count;
+        let mut bounds = c.bounds;
+
+        let (cx, cy) = cell(c.pos);
+        for x in cx - 1..=cx + 1 {
+            for y in cy - 1..=cy + 1 {
+                for &j in grid.get(&(x, y)).into_iter().flatten() {
+                    let other = &clusters[j];
+                    if merged[j] || distance(c.pos, other.pos) > radius {
+                        continue;
+                    }
+                    merged[j] = true;
+
+                    sum += other.pos * other.count as f64;
+                    count += other.count;
+                    bounds = geo::Rect::new(
+                        geo::coord! {
+                            x: bounds.min().x.min(other.bounds.min().x),
+                            y: bounds.min().y.min(other.bounds.min().y),
+                        },
+                        geo::coord! {
+                            x: bounds.max().x.max(other.bounds.max().x),
+                            y: bounds.max().y.max(other.bounds.max().y),
+                        },
+                    );
+                }
+            }
+        }
+
+        result.push(Cluster {
+            pos: sum / count as f64,
+            count,
+            bounds,
+        });
+    }
+
+    result
+}
+
+fn distance(a: Point<f64>, b: Point<f64>) -> f64 {
+    (a.x() - b.x()).hypot(a.y() - b.y())
+}
+
+impl Bubble {
+    pub fn contains(&self, pos: Pos2) -> bool {
+        self.center.distance(pos) <= self.radius
+    }
+}
@@ -0,0 +1,128 @@
+use geo::{
+    Coord, GeodesicDestination, GeodesicIntermediate, LineString, MapCoords, MultiPolygon, Point,
+    Polygon,
+};
+
+/// The latitude web mercator maps to the edge of the map, slightly exceeded so polar caps cover
+/// it completely.
+const POLE_LATITUDE: f64 = 85.06;
+
+const CIRCLE_SEGMENTS: usize = 128;
+
+/// The longest piece of a geodesic drawn as a straight line, in meters.
+const MAX_SEGMENT_LENGTH: f64 = 50_000.0;
+
+/// The area within `radius` meters of `center` on the WGS 84 ellipsoid.
+///
+/// A circle around a pole is cut at the antimeridian and closed along the edge of the map, one
+/// crossing the antimeridian otherwise is repeated on the other side, so it shows up on both
+/// ends of the map.
+pub(crate) fn circle(center: Point<f64>, radius: f64) -> MultiPolygon<f64> {
+    let mut coords = (0..CIRCLE_SEGMENTS)
+        .map(|i| {
+            let bearing = i as f64 * 360.0 / CIRCLE_SEGMENTS as f64;
+            Coord::from(center.geodesic_destination(bearing, radius))
+        })
+        .collect::<Vec<_>>();
+
+    // around a pole the ring doesn't close but runs once around the globe, crossing the
+    // antimeridian a single time
+    let winding: f64 = (0..coords.len())
+        .map(|i| {
+            let (a, b) = (coords[i], coords[(i + 1) % coords.len()]);
+            unwrap_longitude(a.x, b.x) - a.x
+        })
+        .sum();
+    if winding.abs() > 180.0 {
+        if let Some(crossing) =
+            (1..coords.len()).find(|&i| (coords[i].x - coords[i - 1].x).abs() > 180.0)
+        {
+            coords.rotate_left(crossing);
+        }
+        let (first, last) = (coords[0], coords[coords.len() - 1]);
+
+        let edge = 180.0f64.copysign(winding);
+        let first_x = unwrap_longitude(last.x, first.x);
+        let t = (edge - last.x) / (first_x - last.x);
+        let lat = last.y + t * (first.y - last.y);
+        let pole = POLE_LATITUDE.copysign(center.y());
+
+        coords.insert(0, Coord { x: -edge, y: lat });
+        coords.extend([
+            Coord { x: edge, y: lat },
+            Coord { x: edge, y: pole },
+            Coord { x: -edge, y: pole },
+        ]);
+        return Polygon::new(LineString::new(coords), Vec::new()).into();
+    }
+
+    for i in 1..coords.len() {
+        coords[i].x = unwrap_longitude(coords[i - 1].x, coords[i].x);
+    }
+    repeat_across_antimeridian(Polygon::new(LineString::new(coords), Vec::new()))
+}
+
+/// The shortest paths over the WGS 84 ellipsoid through `points`, split where they cross the
+/// antimeridian so each part stays within ±180° longitude.
+pub(crate) fn line_string(points: &[Point<f64>]) -> Vec<Vec<Point<f64>>> {
+    let mut dense = Vec::new();
+    for pair in points.windows(2) {
+        dense.extend(pair[0].geodesic_intermediate_fill(&pair[1], MAX_SEGMENT_LENGTH, true));
+        // the end is repeated as the start of the next segment
+        dense.pop();
+    }
+    dense.extend(points.last());
+
+    let mut parts = Vec::new();
+    let mut part: Vec<Point<f64>> = Vec::new();
+    for p in dense {
+        if let Some(&prev) = part.last()
+            && (p.x() - prev.x()).abs() > 180.0
+        {
+            let x = unwrap_longitude(prev.x(), p.x());
+            let edge = 180.0f64.copysign(x - prev.x());
+            let t = (edge - prev.x()) / (x - prev.x());
+            let lat = prev.y() + t * (p.y() - prev.y());
+
+            part.push(Point::new(edge, lat));
+            parts.push(std::mem::take(&mut part));
+            part.push(Point::new(-edge, lat));
+        }
+        part.push(p);
+    }
+    parts.push(part);
+
+    parts
+}
+
+/// `lon` moved by whole turns to be within 180° of `prev`.
+fn unwrap_longitude(prev: f64, lon: f64) -> f64 {
+    prev + (lon - prev + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Adds copies shifted by a full turn of polygons reaching beyond ±180° longitude.
+fn repeat_across_antimeridian(polygon: Polygon<f64>) -> MultiPolygon<f64> {
+    let (min, max) = polygon
+        .exterior()
+        .coords()
+        .fold((f64::MAX, f64::MIN), |(min, max), c| {
+            (min.min(c.x), max.max(c.x))
+        });
+
+    let mut polygons = vec![];
+    if max > 180.0 {
+        polygons.push(polygon.map_coords(|c| Coord {
+            x: c.x - 360.0,
+            ..c
+        }));
+    }
+    if min < -180.0 {
+        polygons.push(polygon.map_coords(|c| Coord {
+            x: c.x + 360.0,
+            ..c
+        }));
+    }
+    polygons.push(polygon);
+
+    MultiPolygon::new(polygons)
+}
@@ -0,0 +1,108 @@
+use std::str::FromStr;
+
+use geojson::{Feature, FeatureCollection, GeoJson, JsonObject};
+
+/// The features of a GeoJSON document, drawn with [`crate::EMap::geojson`].
+///
+/// ```no_run
+/// use egui::{Color32, Stroke};
+/// use emap::{EMap, GeoJsonLayer, GeometryStyle};
+///
+/// let layer: GeoJsonLayer = std::fs::read_to_string("parcels.geojson")
+///     .unwrap()
+///     .parse()
+///     .unwrap();
+///
+/// let map = EMap::new("map").geojson(&layer, |properties| {
+///     let color = match properties.get("zone").and_then(|z| z.as_str()) {
+///         Some("residential") => Color32::YELLOW,
+///         _ => Color32::GRAY,
+///     };
+///     GeometryStyle::new()
+///         .stroke(Stroke::new(1.0, color))
+///         .fill(color.gamma_multiply(0.3))
+/// });
+/// ```
+pub struct GeoJsonLayer {
+    features: Vec<Feature>,
+    geometries: Vec<Option<geo::Geometry<f64>>>,
+
+    /// Handed to the style callback for features without properties.
+    no_properties: JsonObject,
+}
+
+/// A feature of a [`GeoJsonLayer`] that was clicked, see [`crate::EMapResponse::clicked_feature`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClickedFeature {
+    /// The number of the layer, counting [`crate::EMap::geojson`] calls from 0.
+    pub layer: usize,
+    /// The index of the feature in [`GeoJsonLayer::features`].
+    pub feature: usize,
+}
+
+impl GeoJsonLayer {
+    pub fn new(collection: FeatureCollection) -> Self {
+        let features = collection.features;
+        let geometries = features
+            .iter()
+            .map(|f| {
+                f.geometry
+                    .clone()
+                    .and_then(|g| geo::Geometry::try_from(g).ok())
+            })
+            .collect();
+
+        Self {
+            features,
+            geometries,
+            no_properties: JsonObject::new(),
+        }
+    }
+
+    pub fn features(&self) -> &[Feature] {
+        &self.features
+    }
+
+    pub fn feature(&self, index: usize) -> Option<&Feature> {
+        self.features.get(index)
+    }
+
+    /// The features that have a geometry, with their index and properties.
+    pub(crate) fn geometries(
+        &self,
+    ) -> impl Iterator<Item = (usize, &JsonObject, &geo::Geometry<f64>)> {
+        self.features
+            .iter()
+            .zip(&self.geometries)
+            .enumerate()
+            .filter_map(|(i, (f, g))| {
+                let properties = f.properties.as_ref().unwrap_or(&self.no_properties);
+                Some((i, properties, g.as_ref()?))
+            })
+    }
+}
+
+impl From<FeatureCollection> for GeoJsonLayer {
+    fn from(collection: FeatureCollection) -> Self {
+        Self::new(collection)
+    }
+}
+
+impl From<GeoJson> for GeoJsonLayer {
+    fn from(geojson: GeoJson) -> Self {
+        let collection = match geojson {
+            GeoJson::FeatureCollection(collection) => collection,
+            GeoJson::Feature(feature) => FeatureCollection::from_iter([feature]),
+            GeoJson::Geometry(geometry) => FeatureCollection::from_iter([Feature::from(geometry)]),
+        };
+        Self::new(collection)
+    }
+}
+
+impl FromStr for GeoJsonLayer {
+    type Err = geojson::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.parse::<GeoJson>()?.into())
+    }
+}
@@ -0,0 +1,195 @@
+use egui::{Color32, Stroke};
+use geo::Geometry;
+
+use crate::{Marker, Shape};
+
+/// How [`crate::EMap::geometry`] draws a geometry: lines and polygon rings with `stroke`,
+/// polygon interiors with `fill` and points as circles of `point_radius` screen points, using
+/// both, or as a `marker`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeometryStyle {
+    pub stroke: Option<Stroke>,
+    pub fill: Option<Color32>,
+    pub point_radius: f32,
+    /// Drawn at every point instead of a circle, its own position is ignored.
+    pub marker: Option<Marker>,
+}
+
+impl Default for GeometryStyle {
+    fn default() -> Self {
+        Self {
+            stroke: Some(Stroke::new(2.0, Color32::from_rgb(51, 136, 255))),
+            fill: None,
+            point_radius: 5.0,
+            marker: None,
+        }
+    }
+}
+
+impl GeometryStyle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stroke(mut self, stroke: impl Into<Option<Stroke>>) -> Self {
+        self.stroke = stroke.into();
+        self
+    }
+
+    pub fn fill(mut self, fill: impl Into<Option<Color32>>) -> Self {
+        self.fill = fill.into();
+        self
+    }
+
+    pub fn point_radius(mut self, radius: f32) -> Self {
+        self.point_radius = radius;
+        self
+    }
+
+    /// Draws points as copies of `marker` placed at them instead of circles.
+    pub fn marker(mut self, marker: impl Into<Option<Marker>>) -> Self {
+        self.marker = marker.into();
+        self
+    }
+
+    fn push_point(&self, p: geo::Point<f64>, shapes: &mut Vec<Shape>, markers: &mut Vec<Marker>) {
+        shapes.push(match &self.marker {
+            Some(marker) => {
+                markers.push(Marker {
+                    position: p,
+                    ..marker.clone()
+                });
+                Shape::Marker(markers.len() - 1)
+            }
+            None => Shape::Circle(p, self.point_radius, self.stroke, self.fill),
+        });
+    }
+}
+
+impl From<Stroke> for GeometryStyle {
+    fn from(stroke: Stroke) -> Self {
+        Self::new().stroke(stroke)
+    }
+}
+
+/// Breaks `geometry` down into the shapes EMap knows how to draw. Style markers go to
+/// `markers`, so they are drawn above all shapes, with a [`Shape::Marker`] referring to them.
+pub(crate) fn push_shapes(
+    geometry: Geometry<f64>,
+    style: GeometryStyle,
+    shapes: &mut Vec<Shape>,
+    markers: &mut Vec<Marker>,
+) {
+    let stroke = style.stroke;
+    let fill = style.fill;
+
+    match geometry {
+        Geometry::Point(p) => style.push_point(p, shapes, markers),
+        Geometry::MultiPoint(points) => {
+            for p in points {
+                style.push_point(p, shapes, markers);
+            }
+        }
+        Geometry::Line(line) => {
+            if let Some(stroke) = stroke {
+                shapes.push(Shape::Line(line.start_point(), line.end_point(), stroke));
+            }
+        }
+        Geometry::LineString(line) => {
+            if let Some(stroke) = stroke {
+                shapes.push(Shape::LineString(line.into_points(), stroke));
+            }
+        }
+        Geometry::MultiLineString(lines) => {
+            if let Some(stroke) = stroke {
+                for line in lines {
+                    shapes.push(Shape::LineString(line.into_points(), stroke));
+                }
+            }
+        }
+        Geometry::Polygon(polygon) => shapes.push(Shape::Polygon(polygon.into(), stroke, fill)),
+        Geometry::MultiPolygon(polygons) => shapes.push(Shape::Polygon(polygons, stroke, fill)),
+        Geometry::Rect(rect) => shapes.push(Shape::Polygon(rect.to_polygon().into(), stroke, fill)),
+        Geometry::Triangle(triangle) => {
+            shapes.push(Shape::Polygon(triangle.to_polygon().into(), stroke, fill))
+        }
+        Geometry::GeometryCollection(collection) => {
+            for geometry in collection {
+                push_shapes(geometry, style.clone(), shapes, markers);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "geojson")]
+impl Shape {
+    /// Whether `pos` lies on the shape as painted, allowing `tolerance` screen points around
+    /// strokes and circles.
+    pub(crate) fn hit(
+        &self,
+        pos: egui::Pos2,
+        tolerance: f32,
+        markers: &[Marker],
+        to_screen: impl Fn(geo::Point<f64>) -> egui::Pos2,
+    ) -> bool {
+        use geo::{Contains, MapCoords, Point};
+
+        let near_line = |points: &mut dyn Iterator<Item = Point<f64>>, width: f32| {
+            let points = points.map(&to_screen).collect::<Vec<_>>();
+            let max = width / 2.0 + tolerance;
+            points
+                .windows(2)
+                .any(|s| segment_distance(pos, s[0], s[1]) <= max)
+        };
+
+        match self {
+            Shape::Line(start, end, stroke) => {
+                near_line(&mut [*start, *end].into_iter(), stroke.width)
+            }
+            Shape::LineString(points, stroke) => {
+                near_line(&mut points.iter().copied(), stroke.width)
+            }
+            Shape::StyledLine(points, style) => {
+                near_line(&mut points.iter().copied(), style.width())
+            }
+            Shape::Circle(center, radius, stroke, _) => {
+                let width = stroke.map_or(0.0, |s| s.width);
+                to_screen(*center).distance(pos) <= radius + width / 2.0 + tolerance
+            }
+            Shape::Marker(index) => markers[*index]
+                .rect(to_screen(markers[*index].position))
+                .expand(tolerance)
+                .contains(pos),
+            Shape::Polygon(polygons, stroke, fill) => {
+                if fill.is_some() {
+                    let screen = polygons.map_coords(|c| {
+                        let p = to_screen(c.into());
+                        geo::coord! { x: p.x as f64, y: p.y as f64 }
+                    });
+                    if screen.contains(&Point::new(pos.x as f64, pos.y as f64)) {
+                        return true;
+                    }
+                }
+
+                stroke.is_some_and(|stroke| {
+                    polygons.iter().any(|polygon| {
+                        std::iter::once(polygon.exterior())
+                            .chain(polygon.interiors())
+                            .any(|ring| near_line(&mut ring.points(), stroke.width))
+                    })
+                })
+            }
+        }
+    }
+}
+
+#[cfg(feature = "geojson")]
+fn segment_distance(p: egui::Pos2, a: egui::Pos2, b: egui::Pos2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_sq() > 0.0 {
+        ((p - a).dot(ab) / ab.length_sq()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    p.distance(a + ab * t)
+}
@@ -0,0 +1,331 @@
+//! Parsing of [GPX](https://www.topografix.com/gpx.asp) files as written by GPS devices and
+//! fitness apps. Waypoints, routes and tracks are read with their names, elevations and
+//! timestamps, extensions are ignored.
+
+use std::str::FromStr;
+
+use egui::{Color32, Stroke};
+use geo::{BoundingRect, MultiPoint, Point};
+use roxmltree::Node;
+
+use crate::{
+    Marker,
+    xml::{child_text, children},
+};
+
+#[derive(Debug)]
+pub enum GpxError {
+    Xml(roxmltree::Error),
+    NotGpx,
+    InvalidCoordinate,
+}
+
+impl std::fmt::Display for GpxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpxError::Xml(e) => write!(f, "invalid gpx xml: {e}"),
+            GpxError::NotGpx => write!(f, "document root is not a gpx element"),
+            GpxError::InvalidCoordinate => write!(f, "gpx point without valid lat/lon"),
+        }
+    }
+}
+
+impl std::error::Error for GpxError {}
+
+impl From<roxmltree::Error> for GpxError {
+    fn from(e: roxmltree::Error) -> Self {
+        GpxError::Xml(e)
+    }
+}
+
+/// A waypoint, route point or track point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waypoint {
+    pub point: Point<f64>,
+    pub name: Option<String>,
+    /// Elevation in meters.
+    pub elevation: Option<f64>,
+    /// The timestamp as written in the file, an ISO 8601 date time in UTC.
+    pub time: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Route {
+    pub name: Option<String>,
+    pub points: Vec<Waypoint>,
+}
+
+/// A recorded track, split into segments wherever the recording was interrupted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Track {
+    pub name: Option<String>,
+    pub segments: Vec<Vec<Waypoint>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Gpx {
+    pub waypoints: Vec<Waypoint>,
+    pub routes: Vec<Route>,
+    pub tracks: Vec<Track>,
+}
+
+impl Gpx {
+    pub fn parse(xml: &str) -> Result<Self, GpxError> {
+        let doc = roxmltree::Document::parse(xml)?;
+        let root = doc.root_element();
+        if root.tag_name().name() != "gpx" {
+            return Err(GpxError::NotGpx);
+        }
+
+        let mut gpx = Gpx::default();
+        for node in root.children() {
+            match node.tag_name().name() {
+                "wpt" => gpx.waypoints.push(waypoint(node)?),
+                "rte" => gpx.routes.push(Route {
+                    name: child_text(node, "name"),
+                    points: children(node, "rtept")
+                        .map(waypoint)
+                        .collect::<Result<_, _>>()?,
+                }),
+                "trk" => gpx.tracks.push(Track {
+                    name: child_text(node, "name"),
+                    segments: children(node, "trkseg")
+                        .map(|seg| children(seg, "trkpt").map(waypoint).collect())
+                        .collect::<Result<_, _>>()?,
+                }),
+                _ => {}
+            }
+        }
+
+        Ok(gpx)
+    }
+
+    /// All waypoints, route points and track points.
+    pub fn points(&self) -> impl Iterator<Item = &Waypoint> {
+        self.waypoints
+            .iter()
+            .chain(self.routes.iter().flat_map(|r| &r.points))
+            .chain(self.tracks.iter().flat_map(|t| t.segments.iter().flatten()))
+    }
+
+    /// The extent of all points, `None` for a file without any.
+    pub fn bounding_rect(&self) -> Option<geo::Rect<f64>> {
+        self.points()
+            .map(|p| p.point)
+            .collect::<MultiPoint<f64>>()
+            .bounding_rect()
+    }
+}
+
+impl FromStr for Gpx {
+    type Err = GpxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+fn waypoint(node: Node) -> Result<Waypoint, GpxError> {
+    let coordinate = |name| {
+        node.attribute(name)
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .ok_or(GpxError::InvalidCoordinate)
+    };
+
+    Ok(Waypoint {
+        point: Point::new(coordinate("lon")?, coordinate("lat")?),
+        name: child_text(node, "name"),
+        elevation: child_text(node, "ele").and_then(|e| e.parse().ok()),
+        time: child_text(node, "time"),
+    })
+}
+
+/// How [`crate::EMap::gpx`] draws a [`Gpx`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpxStyle {
+    pub track: Stroke,
+    pub route: Stroke,
+    /// Placed at every waypoint with the name of the waypoint as its label, its own position
+    /// and label are ignored.
+    pub waypoint: Marker,
+}
+
+impl Default for GpxStyle {
+    fn default() -> Self {
+        Self {
+            track: Stroke::new(3.0, Color32::from_rgb(230, 40, 40)),
+            route: Stroke::new(2.0, Color32::from_rgb(51, 136, 255)),
+            waypoint: Marker::new(Point::new(0.0, 0.0)),
+        }
+    }
+}
+
+impl GpxStyle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn track(mut self, stroke: Stroke) -> Self {
+        self.track = stroke;
+        self
+    }
+
+    pub fn route(mut self, stroke: Stroke) -> Self {
+        self.route = stroke;
+        self
+    }
+
+    pub fn waypoint(mut self, marker: Marker) -> Self {
+        self.waypoint = marker;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GPX_1_1: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <wpt lat="47.5" lon="8.25">
+    <ele>412.5</ele>
+    <name>Summit</name>
+    <time>2024-05-01T10:00:00Z</time>
+  </wpt>
+  <wpt lat="47.6" lon="8.3"/>
+  <rte>
+    <name>Detour</name>
+    <rtept lat="47.0" lon="8.0"/>
+    <rtept lat="47.1" lon="8.1"/>
+  </rte>
+  <trk>
+    <name>Morning ride</name>
+    <trkseg>
+      <trkpt lat="47.2" lon="8.2"><ele>400</ele></trkpt>
+      <trkpt lat="47.3" lon="8.3"/>
+    </trkseg>
+    <trkseg>
+      <trkpt lat="47.4" lon="8.4"/>
+    </trkseg>
+    <extensions><color>red</color></extensions>
+  </trk>
+</gpx>"#;
+
+    fn point(lat: f64, lon: f64) -> Waypoint {
+        Waypoint {
+            point: Point::new(lon, lat),
+            name: None,
+            elevation: None,
+            time: None,
+        }
+    }
+
+    #[test]
+    fn waypoints() {
+        let gpx = Gpx::parse(GPX_1_1).unwrap();
+        assert_eq!(
+            gpx.waypoints,
+            vec![
+                Waypoint {
+                    point: Point::new(8.25, 47.5),
+                    name: Some("Summit".to_string()),
+                    elevation: Some(412.5),
+                    time: Some("2024-05-01T10:00:00Z".to_string()),
+                },
+                point(47.6, 8.3),
+            ]
+        );
+    }
+
+    #[test]
+    fn routes() {
+        let gpx = Gpx::parse(GPX_1_1).unwrap();
+        assert_eq!(
+            gpx.routes,
+            vec![Route {
+                name: Some("Detour".to_string()),
+                points: vec![point(47.0, 8.0), point(47.1, 8.1)],
+            }]
+        );
+    }
+
+    #[test]
+    fn track_segments() {
+        let gpx = Gpx::parse(GPX_1_1).unwrap();
+        assert_eq!(
+            gpx.tracks,
+            vec![Track {
+                name: Some("Morning ride".to_string()),
+                segments: vec![
+                    vec![
+                        Waypoint {
+                            elevation: Some(400.0),
+                            ..point(47.2, 8.2)
+                        },
+                        point(47.3, 8.3)
+                    ],
+                    vec![point(47.4, 8.4)],
+                ],
+            }]
+        );
+
+        let bounds = gpx.bounding_rect().unwrap();
+        assert_eq!(bounds.min(), geo::coord! { x: 8.0, y: 47.0 });
+        assert_eq!(bounds.max(), geo::coord! { x: 8.4, y: 47.6 });
+    }
+
+    #[test]
+    fn gpx_1_0() {
+        let gpx = Gpx::parse(
+            r#"<gpx version="1.0" xmlns="http://www.topografix.com/GPX/1/0">
+                 <wpt lat="1.5" lon="2.5"><name>Camp</name></wpt>
+                 <trk><trkseg><trkpt lat="1" lon="2"/></trkseg></trk>
+               </gpx>"#,
+        )
+        .unwrap();
+        assert_eq!(gpx.waypoints[0].point, Point::new(2.5, 1.5));
+        assert_eq!(gpx.waypoints[0].name.as_deref(), Some("Camp"));
+        assert_eq!(gpx.tracks[0].segments, vec![vec![point(1.0, 2.0)]]);
+    }
+
+    #[test]
+    fn prefixed_namespace() {
+        let gpx = Gpx::parse(
+            r#"<g:gpx xmlns:g="http://www.topografix.com/GPX/1/1">
+                 <g:wpt lat="1" lon="2"><g:ele>3</g:ele></g:wpt>
+               </g:gpx>"#,
+        )
+        .unwrap();
+        assert_eq!(gpx.waypoints[0].elevation, Some(3.0));
+    }
+
+    #[test]
+    fn invalid_coordinates() {
+        for wpt in [
+            r#"<wpt lon="2"/>"#,
+            r#"<wpt lat="1"/>"#,
+            r#"<wpt lat="north" lon="2"/>"#,
+            r#"<wpt lat="1" lon=""/>"#,
+        ] {
+            assert!(
+                matches!(
+                    Gpx::parse(&format!("<gpx>{wpt}</gpx>")),
+                    Err(GpxError::InvalidCoordinate)
+                ),
+                "{wpt}"
+            );
+        }
+        assert!(matches!(
+            Gpx::parse(r#"<gpx><trk><trkseg><trkpt lat="1"/></trkseg></trk></gpx>"#),
+            Err(GpxError::InvalidCoordinate)
+        ));
+    }
+
+    #[test]
+    fn not_gpx() {
+        assert!(matches!(Gpx::parse("<kml/>"), Err(GpxError::NotGpx)));
+        assert!(matches!(Gpx::parse("<gpx>"), Err(GpxError::Xml(_))));
+        assert_eq!(Gpx::parse("<gpx/>").unwrap(), Gpx::default());
+        assert_eq!("<gpx/>".parse::<Gpx>().unwrap().bounding_rect(), None);
+    }
+}
@@ -0,0 +1,210 @@
+use std::sync::Mutex;
+
+use egui::{Color32, ColorImage, Context, Painter, Pos2, Rect, TextureHandle, TextureOptions};
+use geo::Point;
+
+use crate::{Interpolate, Projector, normalized_mercator};
+
+/// The density of many weighted points, drawn as a colored overlay with [`crate::EMap::heatmap`].
+///
+/// The density is estimated on the CPU for the visible part of the map, at a reduced
+/// resolution, and only recomputed when zooming or panning far enough.
+///
+/// ```no_run
+/// use emap::{EMap, HeatmapLayer};
+///
+/// fn pings_ui(ui: &mut egui::Ui, heatmap: &HeatmapLayer) {
+///     EMap::new("map").heatmap(heatmap).show(ui);
+/// }
+///
+/// # let pings: Vec<geo::Point<f64>> = Vec::new();
+/// let heatmap = HeatmapLayer::from_points(pings).radius(20.0).intensity(0.5);
+/// ```
+pub struct HeatmapLayer {
+    /// Normalized mercator positions with their weights.
+    points: Vec<(Point<f64>, f32)>,
+
+    radius: f32,
+    intensity: f32,
+    gradient: Vec<Color32>,
+    opacity: f32,
+    resolution: f32,
+
+    cache: Mutex<Option<Cache>>,
+}
+
+struct Cache {
+    zoom: f64,
+    /// The normalized mercator area covered by the texture.
+    region: geo::Rect<f64>,
+    texture: TextureHandle,
+}
+
+impl HeatmapLayer {
+    pub fn new(points: impl IntoIterator<Item = (Point<f64>, f32)>) -> Self {
+        Self {
+            points: points
+                .into_iter()
+                .map(|(p, w)| (normalized_mercator(p), w))
+                .collect(),
+
+            radius: 25.0,
+            intensity: 1.0,
+            gradient: Vec::new(),
+            opacity: 1.0,
+            resolution: 4.0,
+
+            cache: Mutex::new(None),
+        }
+        .gradient(&[
+            (0.0, Color32::TRANSPARENT),
+            (0.2, Color32::from_rgba_unmultiplied(0, 0, 255, 120)),
+            (0.4, Color32::BLUE),
+            (0.6, Color32::from_rgb(0, 255, 255)),
+            (0.7, Color32::from_rgb(0, 255, 0)),
+            (0.8, Color32::YELLOW),
+            (1.0, Color32::RED),
+        ])
+    }
+
+    /// Points with a weight of 1.
+    pub fn from_points(points: impl IntoIterator<Item = Point<f64>>) -> Self {
+        Self::new(points.into_iter().map(|p| (p, 1.0)))
+    }
+
+    /// The radius of influence of each point in screen points.
+    pub fn radius(mut self, radius: f32) -> Self {
+        self.radius = radius.max(1.0);
+        self
+    }
+
+    /// Scales the density before it is colored. The color at the center of a single point of
+    /// weight 1 is 63% along the gradient at intensity 1, more intense areas approach its end.
+    pub fn intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Colors from low to high density, as `(position, color)` stops with positions from `0.0`
+    /// to `1.0`. Transparent colors let the map show through sparse areas.
+    pub fn gradient(mut self, stops: &[(f32, Color32)]) -> Self {
+        self.gradient = (0..256)
+            .map(|i| {
+                let t = i as f32 / 255.0;
+                let upper = stops.iter().position(|(s, _)| *s >= t);
+                match upper {
+                    Some(0) => stops[0].1,
+                    Some(u) => {
+                        let ((s0, c0), (s1, c1)) = (stops[u - 1], stops[u]);
+                        c0.interpolate(&c1, ((t - s0) / (s1 - s0)) as f64)
+                    }
+                    None => stops.last().map_or(Color32::TRANSPARENT, |(_, c)| *c),
+                }
+            })
+            .collect();
+        self
+    }
+
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+
+    /// The size of the cells the density is estimated for, in screen points. Smaller cells look
+    /// smoother but take longer to compute.
+    pub fn resolution(mut self, cell_size: f32) -> Self {
+        self.resolution = cell_size.max(1.0);
+        self
+    }
+
+    /// Paints the heatmap over the map as `projector` draws it. The density is kept for as long
+    /// as the zoom of the projector doesn't change and the view stays within the computed region.
+    pub(crate) fn paint(&self, painter: &Painter, ctx: &Context, projector: &Projector) {
+        let rect = projector.rect();
+        let zoom = projector.zoom();
+        let from_screen = |pos| projector.screen_to_normalized(pos);
+        let to_screen = |p| projector.normalized_to_screen(p);
+
+        let visible = geo::Rect::new(from_screen(rect.min), from_screen(rect.max));
+
+        let mut cache = self.cache.lock().unwrap();
+        let valid = cache.as_ref().is_some_and(|c| {
+            c.zoom == zoom
+                && c.region.min().x <= visible.min().x
+                && c.region.min().y <= visible.min().y
+                && c.region.max().x >= visible.max().x
+                && c.region.max().y >= visible.max().y
+        });
+        if !valid {
+            // half a view of margin on every side, so panning doesn't recompute it right away
+            let padded = rect.expand2(rect.size() / 2.0);
+            let region = geo::Rect::new(from_screen(padded.min), from_screen(padded.max));
+            let image = self.density_image(region, padded.size());
+            let texture = match cache.take() {
+                Some(mut c) => {
+                    c.texture.set(image, TextureOptions::LINEAR);
+                    c.texture
+                }
+                None => ctx.load_texture("heatmap", image, TextureOptions::LINEAR),
+            };
+            *cache = Some(Cache {
+                zoom,
+                region,
+                texture,
+            });
+        }
+
+        let cache = cache.as_ref().unwrap();
+        let top_left = Point::new(cache.region.min().x, cache.region.min().y);
+        let bottom_right = Point::new(cache.region.max().x, cache.region.max().y);
+        painter.image(
+            cache.texture.id(),
+            Rect::from_two_pos(to_screen(top_left), to_screen(bottom_right)),
+            Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+            Color32::WHITE.gamma_multiply(self.opacity),
+        );
+    }
+
+    /// Estimates the density over `region`, which is `size` screen points large.
+    fn density_image(&self, region: geo::Rect<f64>, size: egui::Vec2) -> ColorImage {
+        let w = (size.x / self.resolution).ceil().max(1.0) as usize;
+        let h = (size.y / self.resolution).ceil().max(1.0) as usize;
+        let r = self.radius / self.resolution;
+        let reach = r.ceil() as isize;
+
+        let mut density = vec![0.0f32; w * h];
+        for (p, weight) in &self.points {
+            let x = ((p.x() - region.min().x) / region.width() * w as f64) as f32;
+            let y = ((p.y() - region.min().y) / region.height() * h as f64) as f32;
+            if x < -r || y < -r || x > w as f32 + r || y > h as f32 + r {
+                continue;
+            }
+
+            let (cx, cy) = (x.floor() as isize, y.floor() as isize);
+            for gy in (cy - reach).max(0)..(cy + reach + 1).min(h as isize) {
+                for gx in (cx - reach).max(0)..(cx + reach + 1).min(w as isize) {
+                    let dx = gx as f32 + 0.5 - x;
+                    let dy = gy as f32 + 0.5 - y;
+                    let d2 = (dx * dx + dy * dy) / (r * r);
+                    if d2 < 1.0 {
+                        // quartic kernel, 1 at the point and 0 at the radius
+                        density[gy as usize * w + gx as usize] += weight * (1.0 - d2).powi(2);
+                    }
+                }
+            }
+        }
+
+        let pixels = density
+            .iter()
+            .map(|d| {
+                let t = 1.0 - (-d * self.intensity).exp();
+                self.gradient[(t.clamp(0.0, 1.0) * 255.0).round() as usize]
+            })
+            .collect();
+
+        ColorImage {
+            size: [w, h],
+            pixels,
+        }
+    }
+}
@@ -0,0 +1,716 @@
+//! Reading of [KML](https://developers.google.com/kml/documentation/kmlreference) documents and
+//! zipped KMZ archives as exported from Google Earth.
+//!
+//! Supported are Placemarks with Point, LineString, LinearRing, Polygon and MultiGeometry
+//! geometries, the colors and widths of shared and inline Styles and StyleMaps, and
+//! GroundOverlays placed by a LatLonBox. Icons, labels, 3D and time features are ignored, points
+//! are drawn as circles in their icon color.
+
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read},
+    str::FromStr,
+    sync::{Arc, OnceLock},
+};
+
+use egui::{Color32, ColorImage, Context, Stroke, TextureHandle, TextureOptions};
+use geo::{Coord, Geometry, LineString, MultiPolygon, Point, Polygon};
+use roxmltree::Node;
+
+use crate::{
+    GeometryStyle, ImageOverlay, Marker, Shape, geometry,
+    xml::{child_text, children},
+};
+
+#[derive(Debug)]
+pub enum KmlError {
+    Xml(roxmltree::Error),
+    Zip(zip::result::ZipError),
+    Io(std::io::Error),
+    Image(image::ImageError),
+    /// The KMZ archive does not contain a `.kml` file.
+    MissingDocument,
+    InvalidCoordinates,
+}
+
+impl std::fmt::Display for KmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KmlError::Xml(e) => write!(f, "invalid kml xml: {e}"),
+            KmlError::Zip(e) => write!(f, "invalid kmz archive: {e}"),
+            KmlError::Io(e) => write!(f, "failed to read kmz archive: {e}"),
+            KmlError::Image(e) => write!(f, "invalid ground overlay image: {e}"),
+            KmlError::MissingDocument => write!(f, "kmz archive contains no kml document"),
+            KmlError::InvalidCoordinates => write!(f, "invalid kml coordinates"),
+        }
+    }
+}
+
+impl std::error::Error for KmlError {}
+
+impl From<roxmltree::Error> for KmlError {
+    fn from(e: roxmltree::Error) -> Self {
+        KmlError::Xml(e)
+    }
+}
+
+impl From<zip::result::ZipError> for KmlError {
+    fn from(e: zip::result::ZipError) -> Self {
+        KmlError::Zip(e)
+    }
+}
+
+impl From<std::io::Error> for KmlError {
+    fn from(e: std::io::Error) -> Self {
+        KmlError::Io(e)
+    }
+}
+
+impl From<image::ImageError> for KmlError {
+    fn from(e: image::ImageError) -> Self {
+        KmlError::Image(e)
+    }
+}
+
+/// The resolved style of a placemark, with KML's defaults for everything left unspecified.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KmlStyle {
+    /// Lines and polygon outlines.
+    pub line: Stroke,
+    /// The fill of polygons, `None` if they are not filled.
+    pub fill: Option<Color32>,
+    /// Whether polygons are outlined with `line`.
+    pub outline: bool,
+    pub icon_color: Color32,
+    pub icon_scale: f32,
+}
+
+impl Default for KmlStyle {
+    fn default() -> Self {
+        Self {
+            line: Stroke::new(1.0, Color32::WHITE),
+            fill: Some(Color32::WHITE),
+            outline: true,
+            icon_color: Color32::WHITE,
+            icon_scale: 1.0,
+        }
+    }
+}
+
+/// The style elements as written in the document, layered on top of each other to resolve
+/// the style of a placemark.
+#[derive(Debug, Clone, Copy, Default)]
+struct PartialStyle {
+    line_color: Option<Color32>,
+    line_width: Option<f32>,
+    poly_color: Option<Color32>,
+    fill: Option<bool>,
+    outline: Option<bool>,
+    icon_color: Option<Color32>,
+    icon_scale: Option<f32>,
+}
+
+impl PartialStyle {
+    fn parse(style: Node) -> Self {
+        let mut s = Self::default();
+        for node in style.children() {
+            match node.tag_name().name() {
+                "LineStyle" => {
+                    s.line_color = child_text(node, "color").and_then(parse_color);
+                    s.line_width = child_text(node, "width").and_then(|w| w.parse().ok());
+                }
+                "PolyStyle" => {
+                    s.poly_color = child_text(node, "color").and_then(parse_color);
+                    s.fill = child_text(node, "fill").map(parse_bool);
+                    s.outline = child_text(node, "outline").map(parse_bool);
+                }
+                "IconStyle" => {
+                    s.icon_color = child_text(node, "color").and_then(parse_color);
+                    s.icon_scale = child_text(node, "scale").and_then(|w| w.parse().ok());
+                }
+                _ => {}
+            }
+        }
+        s
+    }
+
+    fn merge(self, over: Self) -> Self {
+        Self {
+            line_color: over.line_color.or(self.line_color),
+            line_width: over.line_width.or(self.line_width),
+            poly_color: over.poly_color.or(self.poly_color),
+            fill: over.fill.or(self.fill),
+            outline: over.outline.or(self.outline),
+            icon_color: over.icon_color.or(self.icon_color),
+            icon_scale: over.icon_scale.or(self.icon_scale),
+        }
+    }
+
+    fn resolve(self) -> KmlStyle {
+        let default = KmlStyle::default();
+        KmlStyle {
+            line: Stroke::new(
+                self.line_width.unwrap_or(default.line.width),
+                self.line_color.unwrap_or(default.line.color),
+            ),
+            fill: self
+                .fill
+                .unwrap_or(true)
+                .then(|| self.poly_color.unwrap_or(Color32::WHITE)),
+            outline: self.outline.unwrap_or(default.outline),
+            icon_color: self.icon_color.unwrap_or(default.icon_color),
+            icon_scale: self.icon_scale.unwrap_or(default.icon_scale),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placemark {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub geometry: Geometry<f64>,
+    pub style: KmlStyle,
+}
+
+/// An image laid over the map, see [`GroundOverlay::set_image`] for loading the images of plain
+/// KML documents.
+pub struct GroundOverlay {
+    pub name: Option<String>,
+    /// The image as referenced by the document, a path inside the archive for KMZ files.
+    pub href: String,
+    pub bounds: geo::Rect<f64>,
+    /// Counterclockwise rotation around the center of `bounds`, in degrees.
+    pub rotation: f32,
+    pub color: Color32,
+
+    image: Option<Arc<ColorImage>>,
+    texture: OnceLock<TextureHandle>,
+}
+
+impl GroundOverlay {
+    pub fn image(&self) -> Option<&Arc<ColorImage>> {
+        self.image.as_ref()
+    }
+
+    pub fn set_image(&mut self, image: impl Into<Arc<ColorImage>>) {
+        self.image = Some(image.into());
+        self.texture = OnceLock::new();
+    }
+
+    /// The loaded image placed at its rotated bounds, or `None` while there is no image.
+    pub(crate) fn overlay(&self, ctx: &Context) -> Option<ImageOverlay> {
+        let image = self.image.as_ref()?;
+        let texture = self.texture.get_or_init(|| {
+            ctx.load_texture(
+                format!("kml:{}", self.href),
+                image.clone(),
+                TextureOptions::LINEAR,
+            )
+        });
+
+        let center = self.bounds.center();
+        let (sin, cos) = (self.rotation as f64).to_radians().sin_cos();
+        let (min, max) = (self.bounds.min(), self.bounds.max());
+        let corners = [
+            (min.x, max.y),
+            (max.x, max.y),
+            (max.x, min.y),
+            (min.x, min.y),
+        ]
+        .map(|(x, y)| {
+            let (dx, dy) = (x - center.x, y - center.y);
+            Point::new(
+                center.x + dx * cos - dy * sin,
+                center.y + dx * sin + dy * cos,
+            )
+        });
+
+        Some(ImageOverlay::from_corners(texture, corners).tint(self.color))
+    }
+}
+
+/// The placemarks and ground overlays of a KML document, drawn with [`crate::EMap::kml`].
+#[derive(Default)]
+pub struct KmlLayer {
+    placemarks: Vec<Placemark>,
+    ground_overlays: Vec<GroundOverlay>,
+}
+
+impl KmlLayer {
+    pub fn parse(xml: &str) -> Result<Self, KmlError> {
+        let doc = roxmltree::Document::parse(xml)?;
+        let root = doc.root_element();
+
+        let mut styles = HashMap::new();
+        for node in root.descendants().filter(|n| n.has_tag_name("Style")) {
+            if let Some(id) = node.attribute("id") {
+                styles.insert(id.to_string(), PartialStyle::parse(node));
+            }
+        }
+        for node in root.descendants().filter(|n| n.has_tag_name("StyleMap")) {
+            let normal = children(node, "Pair")
+                .find(|pair| child_text(*pair, "key").as_deref() == Some("normal"));
+            if let (Some(id), Some(pair)) = (node.attribute("id"), normal) {
+                let style = style_of(pair, &styles);
+                styles.insert(id.to_string(), style);
+            }
+        }
+
+        let mut layer = Self::default();
+        for node in root.descendants() {
+            match node.tag_name().name() {
+                "Placemark" => {
+                    let geometry = node.children().find_map(|n| geometry(n).transpose());
+                    if let Some(geometry) = geometry {
+                        layer.placemarks.push(Placemark {
+                            name: child_text(node, "name"),
+                            description: child_text(node, "description"),
+                            geometry: geometry?,
+                            style: style_of(node, &styles).resolve(),
+                        });
+                    }
+                }
+                "GroundOverlay" => {
+                    let href = children(node, "Icon").find_map(|icon| child_text(icon, "href"));
+                    let bounds = children(node, "LatLonBox").next();
+                    if let (Some(href), Some(bounds)) = (href, bounds) {
+                        let edge = |name| {
+                            child_text(bounds, name)
+                                .and_then(|v| v.parse::<f64>().ok())
+                                .ok_or(KmlError::InvalidCoordinates)
+                        };
+                        layer.ground_overlays.push(GroundOverlay {
+                            name: child_text(node, "name"),
+                            href,
+                            bounds: geo::Rect::new(
+                                Coord {
+                                    x: edge("west")?,
+                                    y: edge("south")?,
+                                },
+                                Coord {
+                                    x: edge("east")?,
+                                    y: edge("north")?,
+                                },
+                            ),
+                            rotation: child_text(bounds, "rotation")
+                                .and_then(|r| r.parse().ok())
+                                .unwrap_or(0.0),
+                            color: child_text(node, "color")
+                                .and_then(parse_color)
+                                .unwrap_or(Color32::WHITE),
+                            image: None,
+                            texture: OnceLock::new(),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(layer)
+    }
+
+    /// Reads a KMZ archive, the zipped KML document along with the images of its ground
+    /// overlays.
+    pub fn from_kmz(bytes: &[u8]) -> Result<Self, KmlError> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+
+        // the main document is called doc.kml by convention, otherwise it is the first one
+        let name = archive
+            .file_names()
+            .filter(|n| n.to_ascii_lowercase().ends_with(".kml"))
+            .min_by_key(|n| *n != "doc.kml")
+            .ok_or(KmlError::MissingDocument)?
+            .to_string();
+        let mut xml = String::new();
+        archive.by_name(&name)?.read_to_string(&mut xml)?;
+
+        let mut layer = Self::parse(&xml)?;
+        for overlay in &mut layer.ground_overlays {
+            let path = overlay.href.trim_start_matches("./");
+            let Ok(mut file) = archive.by_name(path) else {
+                // e.g. an image on a web server
+                continue;
+            };
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+
+            let image = image::load_from_memory(&data)?;
+            let size = [image.width() as _, image.height() as _];
+            let image_buffer = image.to_rgba8();
+            let pixels = image_buffer.as_flat_samples();
+            overlay.set_image(ColorImage::from_rgba_unmultiplied(size, pixels.as_slice()));
+        }
+
+        Ok(layer)
+    }
+
+    pub fn placemarks(&self) -> &[Placemark] {
+        &self.placemarks
+    }
+
+    pub fn ground_overlays(&self) -> &[GroundOverlay] {
+        &self.ground_overlays
+    }
+
+    pub fn ground_overlays_mut(&mut self) -> &mut [GroundOverlay] {
+        &mut self.ground_overlays
+    }
+}
+
+impl FromStr for KmlLayer {
+    type Err = KmlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Breaks a placemark down into the shapes EMap knows how to draw.
+pub(crate) fn push_shapes(
+    geometry: &Geometry<f64>,
+    style: &KmlStyle,
+    shapes: &mut Vec<Shape>,
+    markers: &mut Vec<Marker>,
+) {
+    let geometry_style = match geometry {
+        Geometry::GeometryCollection(collection) => {
+            for geometry in collection {
+                push_shapes(geometry, style, shapes, markers);
+            }
+            return;
+        }
+        Geometry::Point(_) | Geometry::MultiPoint(_) => GeometryStyle::new()
+            .stroke(Stroke::new(1.0, Color32::BLACK))
+            .fill(style.icon_color)
+            .point_radius(5.0 * style.icon_scale),
+        Geometry::Polygon(_)
+        | Geometry::MultiPolygon(_)
+        | Geometry::Rect(_)
+        | Geometry::Triangle(_) => GeometryStyle::new()
+            .stroke(style.outline.then_some(style.line))
+            .fill(style.fill),
+        Geometry::Line(_) | Geometry::LineString(_) | Geometry::MultiLineString(_) => {
+            GeometryStyle::new().stroke(style.line)
+        }
+    };
+    geometry::push_shapes(geometry.clone(), geometry_style, shapes, markers);
+}
+
+/// The shared style referenced by `node`'s styleUrl, overridden by an inline Style.
+fn style_of(node: Node, styles: &HashMap<String, PartialStyle>) -> PartialStyle {
+    let shared = child_text(node, "styleUrl")
+        .and_then(|url| Some(url.rsplit_once('#')?.1.to_string()))
+        .and_then(|id| styles.get(&id).copied())
+        .unwrap_or_default();
+    match children(node, "Style").next() {
+        Some(inline) => shared.merge(PartialStyle::parse(inline)),
+        None => shared,
+    }
+}
+
+fn geometry(node: Node) -> Result<Option<Geometry<f64>>, KmlError> {
+    let coordinates = |node: Node| {
+        children(node, "coordinates")
+            .next()
+            .map(|c| parse_coordinates(c.text().unwrap_or_default()))
+            .unwrap_or(Err(KmlError::InvalidCoordinates))
+    };
+
+    let geometry = match node.tag_name().name() {
+        "Point" => {
+            let coords = coordinates(node)?;
+            let first = coords.first().ok_or(KmlError::InvalidCoordinates)?;
+            Point::from(*first).into()
+        }
+        "LineString" | "LinearRing" => LineString::new(coordinates(node)?).into(),
+        "Polygon" => {
+            let ring = |boundary: Node| {
+                children(boundary, "LinearRing")
+                    .map(|r| coordinates(r).map(LineString::new))
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            let exterior = children(node, "outerBoundaryIs")
+                .next()
+                .map(ring)
+                .transpose()?
+                .and_then(|rings| rings.into_iter().next())
+                .ok_or(KmlError::InvalidCoordinates)?;
+            let interiors = children(node, "innerBoundaryIs")
+                .map(ring)
+                .collect::<Result<Vec<_>, _>>()?;
+            Polygon::new(exterior, interiors.into_iter().flatten().collect()).into()
+        }
+        "MultiGeometry" => {
+            let members = node
+                .children()
+                .filter_map(|n| geometry(n).transpose())
+                .collect::<Result<Vec<_>, _>>()?;
+            // keep polygons together so they are filled as one
+            if members.iter().all(|g| matches!(g, Geometry::Polygon(_))) {
+                let polygons = members.into_iter().filter_map(|g| match g {
+                    Geometry::Polygon(p) => Some(p),
+                    _ => None,
+                });
+                MultiPolygon::from_iter(polygons).into()
+            } else {
+                Geometry::GeometryCollection(members.into_iter().collect())
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(geometry))
+}
+
+/// Parses whitespace separated `lon,lat[,alt]` tuples.
+fn parse_coordinates(text: &str) -> Result<Vec<Coord<f64>>, KmlError> {
+    text.split_whitespace()
+        .map(|tuple| {
+            let mut parts = tuple.split(',').map(|v| v.parse::<f64>());
+            match (parts.next(), parts.next()) {
+                (Some(Ok(x)), Some(Ok(y))) => Ok(Coord { x, y }),
+                _ => Err(KmlError::InvalidCoordinates),
+            }
+        })
+        .collect()
+}
+
+/// KML colors are hex `aabbggrr`.
+fn parse_color(text: String) -> Option<Color32> {
+    let v = u32::from_str_radix(text.trim_start_matches('#'), 16).ok()?;
+    let [a, b, g, r] = v.to_be_bytes();
+    Some(Color32::from_rgba_unmultiplied(r, g, b, a))
+}
+
+fn parse_bool(text: String) -> bool {
+    !matches!(text.as_str(), "0" | "false")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use geo::{GeometryCollection, polygon};
+
+    use super::*;
+
+    #[test]
+    fn colors() {
+        assert_eq!(
+            parse_color("7f0000ff".to_string()),
+            Some(Color32::from_rgba_unmultiplied(255, 0, 0, 127))
+        );
+        assert_eq!(
+            parse_color("#ff102030".to_string()),
+            Some(Color32::from_rgb(0x30, 0x20, 0x10))
+        );
+        assert_eq!(parse_color("red".to_string()), None);
+    }
+
+    #[test]
+    fn shared_and_inline_styles() {
+        let layer = KmlLayer::parse(
+            r##"<kml xmlns="http://www.opengis.net/kml/2.2"><Document>
+              <Style id="thick">
+                <LineStyle><color>ff0000ff</color><width>4</width></LineStyle>
+                <PolyStyle><fill>0</fill></PolyStyle>
+              </Style>
+              <Style id="highlight">
+                <LineStyle><color>ff00ff00</color></LineStyle>
+              </Style>
+              <StyleMap id="road">
+                <Pair><key>highlight</key><styleUrl>#highlight</styleUrl></Pair>
+                <Pair><key>normal</key><styleUrl>#thick</styleUrl></Pair>
+              </StyleMap>
+              <Placemark>
+                <styleUrl>#road</styleUrl>
+                <LineString><coordinates>0,0 1,1</coordinates></LineString>
+              </Placemark>
+              <Placemark>
+                <styleUrl>#thick</styleUrl>
+                <Style><LineStyle><width>2</width></LineStyle></Style>
+                <LineString><coordinates>0,0 1,1</coordinates></LineString>
+              </Placemark>
+              <Placemark>
+                <styleUrl>#missing</styleUrl>
+                <Point><coordinates>1,2,3</coordinates></Point>
+              </Placemark>
+            </Document></kml>"##,
+        )
+        .unwrap();
+
+        let styles = layer
+            .placemarks()
+            .iter()
+            .map(|p| p.style)
+            .collect::<Vec<_>>();
+        let thick = KmlStyle {
+            line: Stroke::new(4.0, Color32::RED),
+            fill: None,
+            ..KmlStyle::default()
+        };
+        assert_eq!(styles[0], thick);
+        assert_eq!(
+            styles[1],
+            KmlStyle {
+                line: Stroke::new(2.0, Color32::RED),
+                ..thick
+            }
+        );
+        assert_eq!(styles[2], KmlStyle::default());
+        assert_eq!(layer.placemarks()[2].geometry, Point::new(1.0, 2.0).into());
+    }
+
+    #[test]
+    fn polygon_boundaries() {
+        let layer = KmlLayer::parse(
+            r#"<kml><Placemark><name>Yard</name><Polygon>
+              <outerBoundaryIs><LinearRing>
+                <coordinates>0,0 10,0 10,10 0,10 0,0</coordinates>
+              </LinearRing></outerBoundaryIs>
+              <innerBoundaryIs><LinearRing>
+                <coordinates>2,2 4,2 4,4 2,2</coordinates>
+              </LinearRing></innerBoundaryIs>
+              <innerBoundaryIs><LinearRing>
+                <coordinates>6,6 8,6 8,8 6,6</coordinates>
+              </LinearRing></innerBoundaryIs>
+            </Polygon></Placemark></kml>"#,
+        )
+        .unwrap();
+
+        let placemark = &layer.placemarks()[0];
+        assert_eq!(placemark.name.as_deref(), Some("Yard"));
+        assert_eq!(
+            placemark.geometry,
+            polygon!(
+                exterior: [(x: 0., y: 0.), (x: 10., y: 0.), (x: 10., y: 10.), (x: 0., y: 10.)],
+                interiors: [
+                    [(x: 2., y: 2.), (x: 4., y: 2.), (x: 4., y: 4.)],
+                    [(x: 6., y: 6.), (x: 8., y: 6.), (x: 8., y: 8.)],
+                ],
+            )
+            .into()
+        );
+
+        let without_outer = "<kml><Placemark><Polygon/></Placemark></kml>";
+        assert!(matches!(
+            KmlLayer::parse(without_outer),
+            Err(KmlError::InvalidCoordinates)
+        ));
+    }
+
+    #[test]
+    fn multi_geometry() {
+        let layer = KmlLayer::parse(
+            r#"<kml><Document>
+              <Placemark><MultiGeometry>
+                <Polygon><outerBoundaryIs><LinearRing>
+                  <coordinates>0,0 1,0 1,1 0,0</coordinates>
+                </LinearRing></outerBoundaryIs></Polygon>
+                <Polygon><outerBoundaryIs><LinearRing>
+                  <coordinates>5,5 6,5 6,6 5,5</coordinates>
+                </LinearRing></outerBoundaryIs></Polygon>
+              </MultiGeometry></Placemark>
+              <Placemark><MultiGeometry>
+                <Point><coordinates>1,2</coordinates></Point>
+                <LineString><coordinates>0,0 1,1</coordinates></LineString>
+              </MultiGeometry></Placemark>
+            </Document></kml>"#,
+        )
+        .unwrap();
+
+        let triangle = |x: f64| polygon![(x: x, y: x), (x: x + 1., y: x), (x: x + 1., y: x + 1.)];
+        assert_eq!(
+            layer.placemarks()[0].geometry,
+            MultiPolygon::new(vec![triangle(0.0), triangle(5.0)]).into()
+        );
+        assert_eq!(
+            layer.placemarks()[1].geometry,
+            Geometry::GeometryCollection(GeometryCollection(vec![
+                Point::new(1.0, 2.0).into(),
+                LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]).into(),
+            ]))
+        );
+    }
+
+    #[test]
+    fn invalid_coordinates() {
+        for coordinates in ["", "1", "1,north", "0,0 1"] {
+            let xml = format!(
+                "<kml><Placemark><LineString><coordinates>{coordinates}</coordinates></LineString></Placemark></kml>"
+            );
+            let result = KmlLayer::parse(&xml).map(|l| l.placemarks().len());
+            // an empty line string is valid, if useless
+            if coordinates.is_empty() {
+                assert_eq!(result.unwrap(), 1);
+            } else {
+                assert!(
+                    matches!(result, Err(KmlError::InvalidCoordinates)),
+                    "{coordinates}"
+                );
+            }
+        }
+    }
+
+    const OVERLAY: &str = r#"<kml><GroundOverlay>
+      <name>Scan</name>
+      <color>80ffffff</color>
+      <Icon><href>files/scan.png</href></Icon>
+      <LatLonBox>
+        <north>48</north><south>47</south><east>9</east><west>8</west>
+        <rotation>15</rotation>
+      </LatLonBox>
+    </GroundOverlay></kml>"#;
+
+    #[test]
+    fn ground_overlay() {
+        let layer = KmlLayer::parse(OVERLAY).unwrap();
+        let overlay = &layer.ground_overlays()[0];
+        assert_eq!(overlay.name.as_deref(), Some("Scan"));
+        assert_eq!(overlay.href, "files/scan.png");
+        assert_eq!(
+            overlay.bounds,
+            geo::Rect::new(Coord { x: 8.0, y: 47.0 }, Coord { x: 9.0, y: 48.0 })
+        );
+        assert_eq!(overlay.rotation, 15.0);
+        assert_eq!(
+            overlay.color,
+            Color32::from_rgba_unmultiplied(255, 255, 255, 128)
+        );
+        assert!(overlay.image().is_none());
+
+        let without_east = OVERLAY.replace("<east>9</east>", "");
+        assert!(matches!(
+            KmlLayer::parse(&without_east),
+            Err(KmlError::InvalidCoordinates)
+        ));
+    }
+
+    #[test]
+    fn kmz() {
+        let mut png = Vec::new();
+        image::RgbaImage::new(2, 3)
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        archive.start_file("other.kml", options).unwrap();
+        archive.write_all(b"<kml/>").unwrap();
+        archive.start_file("doc.kml", options).unwrap();
+        archive.write_all(OVERLAY.as_bytes()).unwrap();
+        archive.start_file("files/scan.png", options).unwrap();
+        archive.write_all(&png).unwrap();
+        let bytes = archive.finish().unwrap().into_inner();
+
+        let layer = KmlLayer::from_kmz(&bytes).unwrap();
+        let image = layer.ground_overlays()[0].image().unwrap();
+        assert_eq!(image.size, [2, 3]);
+
+        assert!(matches!(
+            KmlLayer::from_kmz(b"not a zip"),
+            Err(KmlError::Zip(_))
+        ));
+    }
+}
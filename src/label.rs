@@ -0,0 +1,155 @@
+use egui::{Align2, Color32, FontId, Painter, Pos2, Rect, Stroke, Vec2};
+use geo::Point;
+
+/// Text placed at a geographic position, see [`crate::EMap::label`].
+///
+/// Where labels overlap, only the one with the highest [`TextLabel::priority`] is shown, or the
+/// first one added among equal priorities.
+///
+/// ```no_run
+/// use egui::{Align2, Vec2};
+/// use emap::{EMap, TextLabel};
+///
+/// let map = EMap::new("map")
+///     .label(TextLabel::new(geo::point!(x: 8.54, y: 47.37), "Zürich").priority(10))
+///     .label(
+///         TextLabel::new(geo::point!(x: 8.55, y: 47.36), "1.2 km")
+///             .anchor(Align2::LEFT_CENTER)
+///             .offset(Vec2::new(6.0, 0.0)),
+///     );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLabel {
+    pub(crate) position: Point<f64>,
+    pub(crate) text: String,
+    pub(crate) font: FontId,
+    pub(crate) color: Color32,
+    pub(crate) halo: Option<Stroke>,
+    pub(crate) offset: Vec2,
+    pub(crate) anchor: Align2,
+    pub(crate) priority: i32,
+}
+
+impl TextLabel {
+    pub fn new(position: Point<f64>, text: impl Into<String>) -> Self {
+        Self {
+            position,
+            text: text.into(),
+            font: FontId::proportional(14.0),
+            color: Color32::BLACK,
+            halo: Some(Stroke::new(1.5, Color32::WHITE)),
+            offset: Vec2::ZERO,
+            anchor: Align2::CENTER_CENTER,
+            priority: 0,
+        }
+    }
+
+    pub fn font(mut self, font: FontId) -> Self {
+        self.font = font;
+        self
+    }
+
+    pub fn color(mut self, color: Color32) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// An outline around the glyphs that keeps the text readable on any background.
+    pub fn halo(mut self, halo: impl Into<Option<Stroke>>) -> Self {
+        self.halo = halo.into();
+        self
+    }
+
+    /// Moves the text away from its position by `offset` screen points.
+    pub fn offset(mut self, offset: Vec2) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Which point of the text is placed at the position, the center by default.
+    pub fn anchor(mut self, anchor: Align2) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub(crate) fn at(&self, pos: Pos2) -> Label {
+        Label {
+            pos: pos + self.offset,
+            text: self.text.clone(),
+            font: self.font.clone(),
+            color: self.color,
+            halo: self.halo,
+            anchor: self.anchor,
+            priority: self.priority,
+        }
+    }
+}
+
+/// A label in screen coordinates, ready to be painted.
+pub(crate) struct Label {
+    pub pos: Pos2,
+    pub text: String,
+    pub font: FontId,
+    pub color: Color32,
+    pub halo: Option<Stroke>,
+    pub anchor: Align2,
+    pub priority: i32,
+}
+
+impl Label {
+    pub fn paint(&self, painter: &Painter) {
+        self.paint_within(painter, None);
+    }
+
+    /// Paints the label unless it overlaps one of `taken`, to which its extent is added.
+    fn paint_within(&self, painter: &Painter, taken: Option<&mut Vec<Rect>>) {
+        let galley = painter.layout_no_wrap(self.text.clone(), self.font.clone(), self.color);
+        let rect = self.anchor.anchor_size(self.pos, galley.size());
+
+        if let Some(taken) = taken {
+            let halo = self.halo.map_or(0.0, |h| h.width);
+            let extent = rect.expand(halo);
+            if taken.iter().any(|r| r.intersects(extent)) {
+                return;
+            }
+            taken.push(extent);
+        }
+
+        if let Some(halo) = self.halo.filter(|h| h.width > 0.0) {
+            for i in 0..8 {
+                let angle = i as f32 * std::f32::consts::FRAC_PI_4;
+                let offset = Vec2::angled(angle) * halo.width;
+                painter.galley_with_override_text_color(
+                    rect.min + offset,
+                    galley.clone(),
+                    halo.color,
+                );
+            }
+        }
+
+        painter.galley(rect.min, galley, self.color);
+    }
+}
+
+/// Paints `labels`, leaving out those that overlap a label of higher priority if
+/// `avoid_collisions` is set.
+pub(crate) fn paint_labels(painter: &Painter, mut labels: Vec<Label>, avoid_collisions: bool) {
+    if !avoid_collisions {
+        for label in &labels {
+            label.paint(painter);
+        }
+        return;
+    }
+
+    // stable, so earlier labels win among equal priorities
+    labels.sort_by_key(|l| std::cmp::Reverse(l.priority));
+    let mut taken = Vec::new();
+    for label in &labels {
+        label.paint_within(painter, Some(&mut taken));
+    }
+}
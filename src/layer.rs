@@ -0,0 +1,179 @@
+use crate::{BasemapTileUrlProvider, TileLoader, TileUrlProvider, VectorTileLayer};
+
+/// A raster tile layer. EMap draws its layers in the order they were added, so later layers end
+/// up on top of earlier ones.
+///
+/// Layers with the same url provider share their tiles.
+///
+/// ```no_run
+/// use emap::{Basemap, EMap, TileLayer};
+///
+/// fn map_ui(ui: &mut egui::Ui, topo: &emap::BasemapTileUrlProvider) {
+///     EMap::new("map").base_layer(TileLayer::basemap(topo)).show(ui);
+/// }
+///
+/// let topo = Basemap::OpenTopoMap.provider().unwrap();
+/// ```
+pub struct TileLayer<'t> {
+    pub(crate) provider: &'t dyn TileUrlProvider,
+    pub(crate) loader: Option<&'t dyn TileLoader>,
+
+    pub(crate) options: LayerOptions,
+}
+
+impl<'t> TileLayer<'t> {
+    pub fn new(provider: &'t dyn TileUrlProvider) -> Self {
+        Self {
+            provider,
+            loader: None,
+
+            options: LayerOptions::default(),
+        }
+    }
+
+    /// A layer of a [`crate::Basemap`], with the tile size and maximum zoom of its
+    /// [`crate::BasemapInfo`]. Its tiles are scaled up when zooming in further.
+    pub fn basemap(provider: &'t BasemapTileUrlProvider) -> Self {
+        let info = provider.basemap().info();
+        Self::new(provider)
+            .max_data_zoom(info.max_zoom)
+            .tile_size(info.tile_size)
+    }
+
+    pub fn tile_loader(mut self, loader: &'t dyn TileLoader) -> Self {
+        self.loader = Some(loader);
+        self
+    }
+
+    /// Opacity between `0.0` (invisible) and `1.0` (opaque).
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.options.set_opacity(opacity);
+        self
+    }
+
+    /// Only draw the layer while the map zoom is within `min_zoom..=max_zoom`.
+    pub fn zoom_range(mut self, min_zoom: u8, max_zoom: u8) -> Self {
+        self.options.set_zoom_range(min_zoom, max_zoom);
+        self
+    }
+
+    /// The highest zoom level the tile source has tiles for, tiles of this level are scaled up
+    /// when zooming in further.
+    pub fn max_data_zoom(mut self, zoom: u8) -> Self {
+        self.options.max_data_zoom = zoom;
+        self
+    }
+
+    /// The size of the tiles in pixels if it differs from the tile size of the map, e.g. `512`
+    /// for a source of high resolution tiles, which are then fetched one zoom level lower.
+    pub fn tile_size(mut self, size: u32) -> Self {
+        self.options.tile_size = Some(size);
+        self
+    }
+
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.options.visible = visible;
+        self
+    }
+}
+
+/// The options every kind of layer has.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct LayerOptions {
+    pub opacity: f32,
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+    pub max_data_zoom: u8,
+    /// The size of the source's tiles, the tile size of the map if `None`.
+    pub tile_size: Option<u32>,
+    pub visible: bool,
+}
+
+impl Default for LayerOptions {
+    fn default() -> Self {
+        Self {
+            opacity: 1.0,
+            min_zoom: 0,
+            max_zoom: u8::MAX,
+            max_data_zoom: u8::MAX,
+            tile_size: None,
+            visible: true,
+        }
+    }
+}
+
+impl LayerOptions {
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0.0, 1.0);
+    }
+
+    pub fn set_zoom_range(&mut self, min_zoom: u8, max_zoom: u8) {
+        self.min_zoom = min_zoom;
+        self.max_zoom = max_zoom;
+    }
+
+    pub fn is_active(&self, zoom: u8) -> bool {
+        self.visible && self.opacity > 0.0 && (self.min_zoom..=self.max_zoom).contains(&zoom)
+    }
+
+    /// The zoom level of the tiles to fetch at map `zoom`, for a map of `map_tile_size`.
+    pub fn data_zoom(&self, zoom: u8, map_tile_size: f64) -> u8 {
+        let offset = match self.tile_size {
+            Some(size) => (size as f64 / map_tile_size).log2().round() as i32,
+            None => 0,
+        };
+        (zoom as i32 - offset).clamp(0, self.max_data_zoom as i32) as u8
+    }
+}
+
+/// Any of the layer kinds EMap can draw below its shapes.
+pub enum Layer<'t> {
+    Tile(TileLayer<'t>),
+    Vector(VectorTileLayer<'t>),
+}
+
+impl<'t> Layer<'t> {
+    pub(crate) fn options(&self) -> &LayerOptions {
+        match self {
+            Layer::Tile(l) => &l.options,
+            Layer::Vector(l) => &l.options,
+        }
+    }
+
+    pub(crate) fn is_active(&self, zoom: u8) -> bool {
+        self.options().is_active(zoom)
+    }
+
+    pub(crate) fn provider(&self) -> &'t dyn TileUrlProvider {
+        match self {
+            Layer::Tile(l) => l.provider,
+            Layer::Vector(l) => l.provider,
+        }
+    }
+
+    pub(crate) fn set_provider(&mut self, provider: &'t dyn TileUrlProvider) {
+        match self {
+            Layer::Tile(l) => l.provider = provider,
+            Layer::Vector(l) => l.provider = provider,
+        }
+    }
+
+    pub(crate) fn set_loader(&mut self, loader: &'t dyn TileLoader) {
+        match self {
+            Layer::Tile(l) => l.loader = Some(loader),
+            Layer::Vector(l) => l.loader = Some(loader),
+        }
+    }
+}
+
+impl<'t> From<TileLayer<'t>> for Layer<'t> {
+    fn from(layer: TileLayer<'t>) -> Self {
+        Layer::Tile(layer)
+    }
+}
+
+impl<'t> From<VectorTileLayer<'t>> for Layer<'t> {
+    fn from(layer: VectorTileLayer<'t>) -> Self {
+        Layer::Vector(layer)
+    }
+}
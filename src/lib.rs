@@ -1,16 +1,84 @@
-use std::{collections::HashMap, ops::Deref};
+use std::{collections::HashMap, ops::Deref, sync::Arc};
 
 use egui::{
-    Color32, Context, CursorIcon, Id, Pos2, Rect, Sense, Stroke, TextureHandle, Vec2, Widget,
+    Align2, Color32, Context, CursorIcon, Id, Pos2, Rect, Sense, Stroke, TextureHandle, Vec2,
+    Widget,
 };
 use egui::{Response, Ui};
-use geo::Point;
-
+use geo::{BoundingRect, MapCoords, MultiPolygon, Point};
+
+mod attribution;
+mod basemap;
+mod camera;
+mod choropleth;
+mod cluster;
+mod geodesic;
+#[cfg(feature = "geojson")]
+mod geojson_layer;
+mod geometry;
+#[cfg(feature = "gpx")]
+mod gpx;
+mod heatmap;
+#[cfg(feature = "kml")]
+mod kml;
+mod label;
+mod layer;
+mod line_style;
+mod marker;
+mod mvt;
+mod overlay;
+mod panel;
+mod projector;
+#[cfg(feature = "mapbox-style")]
+mod style;
+mod tessellation;
 mod tile_loader;
 mod url_provider;
-
+mod vector;
+#[cfg(any(feature = "wkt", feature = "wkb"))]
+mod well_known;
+#[cfg(any(feature = "gpx", feature = "kml"))]
+mod xml;
+
+pub use crate::attribution::Attribution;
+pub use crate::basemap::*;
+pub use crate::camera::{Camera, Easing, Transition};
+pub use crate::choropleth::{Choropleth, ColorScale, Legend};
+pub use crate::cluster::ClusterLayer;
+#[cfg(feature = "geojson")]
+pub use crate::geojson_layer::{ClickedFeature, GeoJsonLayer};
+pub use crate::geometry::GeometryStyle;
+#[cfg(feature = "gpx")]
+pub use crate::gpx::{Gpx, GpxError, GpxStyle, Route, Track, Waypoint};
+pub use crate::heatmap::HeatmapLayer;
+#[cfg(feature = "kml")]
+pub use crate::kml::{GroundOverlay, KmlError, KmlLayer, KmlStyle, Placemark};
+pub use crate::label::TextLabel;
+pub use crate::layer::{Layer, TileLayer};
+pub use crate::line_style::{LineArrows, LineCap, LineJoin, LinePattern, LineStyle};
+pub use crate::marker::{Marker, MarkerIcon};
+pub use crate::mvt::*;
+pub use crate::overlay::ImageOverlay;
+pub use crate::projector::Projector;
+#[cfg(feature = "mapbox-style")]
+pub use crate::style::StyleError;
 pub use crate::tile_loader::*;
 pub use crate::url_provider::*;
+pub use crate::vector::{
+    FeatureFilter, FeatureText, Interpolate, TextPaint, VectorPaint, VectorRule, VectorStyle,
+    VectorTileLayer, ZoomStroke, ZoomValue,
+};
+#[cfg(any(feature = "wkt", feature = "wkb"))]
+pub use crate::well_known::WellKnownError;
+#[cfg(feature = "wkt")]
+pub use crate::well_known::geometry_from_wkt;
+#[cfg(feature = "wkb")]
+pub use crate::well_known::{geometry_from_hex_wkb, geometry_from_wkb};
+
+use crate::camera::{Animation, View};
+use crate::panel::CornerPanels;
+use crate::tessellation::Triangulation;
+use crate::vector::{LoadingTile, PreparedTile};
 
 #[derive(Clone)]
 struct EMapState {
@@ -19,7 +87,13 @@ struct EMapState {
     x: f64,
     y: f64,
 
-    registered_tile_textures: HashMap<TileId, TextureHandle>,
+    /// Tiles by their url, so layers of the same provider share them.
+    registered_tile_textures: HashMap<String, TextureHandle>,
+    vector_tiles: HashMap<String, LoadingTile>,
+
+    /// Bounds and padding to fit into the view once the size of the widget is known.
+    fit: Option<(geo::Rect<f64>, f32)>,
+    animation: Option<Animation>,
 }
 
 impl EMapState {
@@ -36,6 +110,10 @@ impl EMapState {
             y,
 
             registered_tile_textures: HashMap::new(),
+            vector_tiles: HashMap::new(),
+
+            fit: None,
+            animation: None,
         }
     }
 
@@ -46,6 +124,10 @@ impl EMapState {
             y: 0.5,
 
             registered_tile_textures: HashMap::new(),
+            vector_tiles: HashMap::new(),
+
+            fit: None,
+            animation: None,
         }
     }
 
@@ -57,19 +139,81 @@ impl EMapState {
         ctx.data_mut(|d| d.insert_temp(id, self));
     }
 
-    fn unload_unused_textures(&mut self, visible_tiles: &[TileId]) {
+    fn camera(&self) -> Camera {
+        Camera::new(
+            reverse_normalized_mercator(Point::new(self.x, self.y)),
+            self.zoom,
+        )
+    }
+
+    fn set_camera(&mut self, camera: Camera) {
+        self.set_view(self.view_of(camera));
+    }
+
+    fn view(&self) -> View {
+        View {
+            x: self.x,
+            y: self.y,
+            zoom: self.zoom,
+        }
+    }
+
+    fn set_view(&mut self, view: View) {
+        self.x = view.x;
+        self.y = view.y;
+        self.zoom = view.zoom;
+    }
+
+    /// Where `camera` moves the map, keeping the zoom if it is NaN.
+    fn view_of(&self, camera: Camera) -> View {
+        let p = normalized_mercator(camera.center);
+        View {
+            x: p.x().clamp(0.0, 1.0),
+            y: p.y().clamp(0.0, 1.0),
+            zoom: match camera.zoom {
+                zoom if zoom.is_nan() => self.zoom,
+                zoom => zoom.clamp(camera::MIN_ZOOM, camera::MAX_ZOOM),
+            },
+        }
+    }
+
+    /// Starts a transition from the current view to `camera` at `time`.
+    fn animate(&mut self, camera: Camera, transition: Transition, fly: bool, time: f64) {
+        self.animation = Some(Animation {
+            from: self.view(),
+            to: self.view_of(camera),
+            start: time,
+            transition,
+            fly,
+        });
+    }
+
+    fn unload_unused_textures(&mut self, visible_tiles: &[String]) {
         let set = visible_tiles
             .iter()
             .collect::<std::collections::HashSet<_>>();
         self.registered_tile_textures.retain(|k, _| set.contains(k));
     }
+
+    fn unload_unused_vector_tiles(&mut self, visible_tiles: &[String]) {
+        let set = visible_tiles
+            .iter()
+            .collect::<std::collections::HashSet<_>>();
+        self.vector_tiles.retain(|k, _| set.contains(k));
+    }
 }
 
 #[derive(Debug, Clone)]
 enum Shape {
     Line(Point<f64>, Point<f64>, Stroke),
     LineString(Vec<Point<f64>>, Stroke),
+    StyledLine(Vec<Point<f64>>, LineStyle),
     Circle(Point<f64>, f32, Option<Stroke>, Option<Color32>),
+    Polygon(MultiPolygon<f64>, Option<Stroke>, Option<Color32>),
+    /// A marker of a [`GeometryStyle`], by its index into [`EMap::markers`], which paints it.
+    /// Only looked up to hit-test GeoJSON features.
+    #[cfg_attr(not(feature = "geojson"), allow(dead_code))]
+    Marker(usize),
 }
 
 pub struct EMapResponse {
@@ -78,12 +222,41 @@ pub struct EMapResponse {
 
     /// The position of the mouse in the map
     pointer_position: Option<Point<f64>>,
+
+    camera: Camera,
+    visible_bounds: geo::Rect<f64>,
+    projector: Projector,
+
+    #[cfg(feature = "geojson")]
+    clicked_feature: Option<ClickedFeature>,
 }
 
 impl EMapResponse {
     pub fn pointer_position(&self) -> Option<Point<f64>> {
         self.pointer_position
     }
+
+    /// The view after this frame's panning and zooming.
+    pub fn camera(&self) -> Camera {
+        self.camera
+    }
+
+    /// Converts between geographic, mercator and screen coordinates as the map was drawn this
+    /// frame, e.g. to paint on top of it.
+    pub fn projector(&self) -> Projector {
+        self.projector
+    }
+
+    /// The longitude/latitude extent of the map widget after this frame's panning and zooming.
+    pub fn visible_bounds(&self) -> geo::Rect<f64> {
+        self.visible_bounds
+    }
+
+    /// The topmost GeoJSON feature under the pointer if the map was clicked this frame.
+    #[cfg(feature = "geojson")]
+    pub fn clicked_feature(&self) -> Option<ClickedFeature> {
+        self.clicked_feature
+    }
 }
 
 impl Deref for EMapResponse {
@@ -94,14 +267,44 @@ impl Deref for EMapResponse {
     }
 }
 
+/// When a callback added with [`EMap::paint`] draws, relative to what the map draws itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PaintOrder {
+    /// Above the tile layers, below image overlays, heatmaps and shapes.
+    AboveTiles,
+    /// Above the shapes, below clusters, markers and labels.
+    AboveShapes,
+    /// Above everything but the legends and the attribution.
+    Top,
+}
+
+type PaintCallback<'t> = Box<dyn Fn(&egui::Painter, Rect, &Projector) + 't>;
+
 pub struct EMap<'t> {
     id: egui::Id,
-    tile_url_provider: &'t dyn TileUrlProvider,
-    tile_loader: Option<&'t dyn TileLoader>,
+    layers: Vec<Layer<'t>>,
+    image_overlays: Vec<ImageOverlay>,
+    heatmaps: Vec<&'t HeatmapLayer>,
+    clusters: Vec<&'t ClusterLayer>,
 
     tile_size: f64,
 
     shapes: Vec<Shape>,
+    markers: Vec<Marker>,
+    labels: Vec<TextLabel>,
+    label_collisions: bool,
+    legends: Vec<Legend>,
+    painters: Vec<(PaintOrder, PaintCallback<'t>)>,
+
+    /// The range of `shapes` each GeoJSON feature was drawn with.
+    #[cfg(feature = "geojson")]
+    feature_shapes: Vec<(std::ops::Range<usize>, ClickedFeature)>,
+    #[cfg(feature = "geojson")]
+    geojson_layers: usize,
+
+    show_attribution: bool,
+    attribution_anchor: Align2,
+    attributions: Vec<Attribution>,
 
     pointer_position: Option<Point<f64>>,
 }
@@ -110,12 +313,28 @@ impl<'t> EMap<'t> {
     pub fn new(id: impl std::hash::Hash) -> Self {
         Self {
             id: Id::new(id),
-            tile_url_provider: &OsmStandardTileUrlProvider,
-            tile_loader: None,
+            layers: vec![TileLayer::new(&OsmStandardTileUrlProvider).into()],
+            image_overlays: Vec::new(),
+            heatmaps: Vec::new(),
+            clusters: Vec::new(),
 
             tile_size: 256.0,
 
             shapes: Vec::new(),
+            markers: Vec::new(),
+            labels: Vec::new(),
+            label_collisions: true,
+            legends: Vec::new(),
+            painters: Vec::new(),
+
+            #[cfg(feature = "geojson")]
+            feature_shapes: Vec::new(),
+            #[cfg(feature = "geojson")]
+            geojson_layers: 0,
+
+            show_attribution: true,
+            attribution_anchor: Align2::RIGHT_BOTTOM,
+            attributions: Vec::new(),
 
             pointer_position: None,
         }
@@ -147,16 +366,154 @@ impl<'t> EMap<'t> {
         self
     }
 
+    /// Draws a line string with dashes, casing, caps, joins or direction arrows.
+    pub fn styled_line_string(
+        mut self,
+        points: Vec<Point<f64>>,
+        style: impl Into<LineStyle>,
+    ) -> Self {
+        self.shapes.push(Shape::StyledLine(points, style.into()));
+        self
+    }
+
+    /// Draws the shortest path over the earth's surface from `start` to `end`, which is a curve
+    /// in the map's projection, e.g. for flight routes. It is split where it crosses the
+    /// antimeridian.
+    pub fn geodesic_line(self, start: Point<f64>, end: Point<f64>, stroke: Stroke) -> Self {
+        self.geodesic_line_string(&[start, end], stroke)
+    }
+
+    /// Like [`EMap::line_string`], but following the shortest paths over the earth's surface,
+    /// see [`EMap::geodesic_line`].
+    pub fn geodesic_line_string(mut self, points: &[Point<f64>], stroke: Stroke) -> Self {
+        for part in geodesic::line_string(points) {
+            self.shapes.push(Shape::LineString(part, stroke));
+        }
+        self
+    }
+
+    /// Sets the url provider of the base layer.
     pub fn tile_url_provider(mut self, provider: &'t dyn TileUrlProvider) -> Self {
-        self.tile_url_provider = provider;
+        self.layers[0].set_provider(provider);
         self
     }
 
+    /// Sets the tile loader of the base layer.
     pub fn tile_loader(mut self, loader: &'t dyn TileLoader) -> Self {
-        self.tile_loader = Some(loader);
+        self.layers[0].set_loader(loader);
+        self
+    }
+
+    /// Replaces the base layer, which is drawn below all other layers.
+    pub fn base_layer(mut self, layer: impl Into<Layer<'t>>) -> Self {
+        self.layers[0] = layer.into();
+        self
+    }
+
+    /// Adds a layer on top of the previously added ones.
+    pub fn layer(mut self, layer: impl Into<Layer<'t>>) -> Self {
+        self.layers.push(layer.into());
+        self
+    }
+
+    /// Adds an image draped over the map, drawn above all layers and below the heatmaps and
+    /// shapes.
+    pub fn image_overlay(mut self, overlay: ImageOverlay) -> Self {
+        self.image_overlays.push(overlay);
+        self
+    }
+
+    /// Adds a heatmap, drawn above all layers and below the shapes.
+    pub fn heatmap(mut self, heatmap: &'t HeatmapLayer) -> Self {
+        self.heatmaps.push(heatmap);
+        self
+    }
+
+    /// Draws the regions of `choropleth` filled by their value, and its legend if it has one.
+    pub fn choropleth(mut self, choropleth: &Choropleth) -> Self {
+        for (polygons, value) in &choropleth.regions {
+            let fill = choropleth.scale.color(*value).or(choropleth.no_data);
+            self.shapes
+                .push(Shape::Polygon(polygons.clone(), choropleth.stroke, fill));
+        }
+        self.legends.extend(choropleth.legend.clone());
+        self
+    }
+
+    /// Shows a legend box in a corner of the map, e.g. for a [`ColorScale`] used to style
+    /// GeoJSON features. Legends in the same corner are stacked in the order they were added.
+    pub fn legend(mut self, legend: Legend) -> Self {
+        self.legends.push(legend);
+        self
+    }
+
+    /// Adds clustered points, drawn above the shapes and below the markers. Clicking a cluster
+    /// zooms in on its points.
+    pub fn clusters(mut self, clusters: &'t ClusterLayer) -> Self {
+        self.clusters.push(clusters);
+        self
+    }
+
+    /// Draws with `paint` while the map is shown, at `order` among the map's own content. It gets
+    /// a painter clipped to the map, the map's screen rect and a projector for placing things.
+    ///
+    /// ```no_run
+    /// use egui::{Align2, Color32, FontId};
+    /// use emap::{EMap, PaintOrder};
+    ///
+    /// fn map_ui(ui: &mut egui::Ui) {
+    ///     EMap::new("map")
+    ///         .paint(PaintOrder::Top, |painter, rect, projector| {
+    ///             let zurich = projector.project(geo::point!(x: 8.54, y: 47.37));
+    ///             painter.circle_filled(zurich, 4.0, Color32::RED);
+    ///             painter.text(
+    ///                 rect.left_top(),
+    ///                 Align2::LEFT_TOP,
+    ///                 format!("zoom {:.1}", projector.zoom()),
+    ///                 FontId::monospace(12.0),
+    ///                 Color32::BLACK,
+    ///             );
+    ///         })
+    ///         .show(ui);
+    /// }
+    /// ```
+    pub fn paint(
+        mut self,
+        order: PaintOrder,
+        paint: impl Fn(&egui::Painter, Rect, &Projector) + 't,
+    ) -> Self {
+        self.painters.push((order, Box::new(paint)));
+        self
+    }
+
+    /// Runs the callbacks added with [`EMap::paint`] for `order`.
+    fn run_painters(&self, order: PaintOrder, painter: &egui::Painter, projector: &Projector) {
+        for (_, paint) in self.painters.iter().filter(|(o, _)| *o == order) {
+            paint(painter, projector.rect(), projector);
+        }
+    }
+
+    /// Show or hide the attribution box. Most tile providers require it to be visible.
+    pub fn show_attribution(mut self, show: bool) -> Self {
+        self.show_attribution = show;
+        self
+    }
+
+    /// The corner of the map the attribution box is placed in, bottom right by default.
+    pub fn attribution_anchor(mut self, anchor: Align2) -> Self {
+        self.attribution_anchor = anchor;
+        self
+    }
+
+    /// Adds an attribution in addition to the ones reported by the tile url provider,
+    /// e.g. for the source of the data drawn on top of the map.
+    pub fn attribution(mut self, attribution: Attribution) -> Self {
+        self.attributions.push(attribution);
         self
     }
 
+    /// Draws a circle of `radius` screen points, which keeps its size on screen when zooming.
+    /// See [`EMap::geodesic_circle`] for a radius in meters.
     pub fn circle(
         mut self,
         center: Point<f64>,
@@ -177,6 +534,156 @@ impl<'t> EMap<'t> {
         self.circle(center, radius, Some(stroke), None)
     }
 
+    /// Draws the area within `radius` meters of `center`, measured along the surface of the
+    /// earth. Unlike [`EMap::circle`] it grows and shrinks with the map, and is distorted by the
+    /// projection just like the geography around it.
+    pub fn geodesic_circle(
+        self,
+        center: Point<f64>,
+        radius: f64,
+        stroke: Option<Stroke>,
+        fill: Option<Color32>,
+    ) -> Self {
+        self.polygon(geodesic::circle(center, radius), stroke, fill)
+    }
+
+    /// Adds a marker, which is drawn on top of all other shapes.
+    pub fn marker(mut self, marker: Marker) -> Self {
+        self.markers.push(marker);
+        self
+    }
+
+    /// Adds a text label, drawn on top of shapes and markers.
+    pub fn label(mut self, label: TextLabel) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    /// Whether labels that overlap labels of higher priority are hidden, which is the default.
+    /// This includes the labels of markers and vector tile layers, the latter with priority `0`.
+    pub fn label_collisions(mut self, avoid: bool) -> Self {
+        self.label_collisions = avoid;
+        self
+    }
+
+    /// Draws a polygon or multi polygon, holes are left unfilled and stroked like the exterior.
+    pub fn polygon(
+        mut self,
+        polygon: impl Into<MultiPolygon<f64>>,
+        stroke: Option<Stroke>,
+        fill: Option<Color32>,
+    ) -> Self {
+        self.shapes
+            .push(Shape::Polygon(polygon.into(), stroke, fill));
+        self
+    }
+
+    pub fn filled_polygon(self, polygon: impl Into<MultiPolygon<f64>>, fill: Color32) -> Self {
+        self.polygon(polygon, None, Some(fill))
+    }
+
+    pub fn stroke_polygon(self, polygon: impl Into<MultiPolygon<f64>>, stroke: Stroke) -> Self {
+        self.polygon(polygon, Some(stroke), None)
+    }
+
+    /// Draws any [`geo::Geometry`], e.g. the result of some geo processing, with `style`.
+    pub fn geometry(
+        mut self,
+        geometry: impl Into<geo::Geometry<f64>>,
+        style: impl Into<GeometryStyle>,
+    ) -> Self {
+        geometry::push_shapes(
+            geometry.into(),
+            style.into(),
+            &mut self.shapes,
+            &mut self.markers,
+        );
+        self
+    }
+
+    /// Draws the features of a GeoJSON layer, styled by `style` from each feature's properties.
+    /// Clicked features are reported by [`EMapResponse::clicked_feature`].
+    #[cfg(feature = "geojson")]
+    pub fn geojson(
+        mut self,
+        layer: &GeoJsonLayer,
+        style: impl Fn(&::geojson::JsonObject) -> GeometryStyle,
+    ) -> Self {
+        let layer_index = self.geojson_layers;
+        self.geojson_layers += 1;
+
+        for (feature, properties, geometry) in layer.geometries() {
+            let start = self.shapes.len();
+            geometry::push_shapes(
+                geometry.clone(),
+                style(properties),
+                &mut self.shapes,
+                &mut self.markers,
+            );
+            self.feature_shapes.push((
+                start..self.shapes.len(),
+                ClickedFeature {
+                    layer: layer_index,
+                    feature,
+                },
+            ));
+        }
+        self
+    }
+
+    /// Draws the tracks and routes of a GPX file as lines and its waypoints as markers labelled
+    /// with their names.
+    #[cfg(feature = "gpx")]
+    pub fn gpx(mut self, gpx: &Gpx, style: GpxStyle) -> Self {
+        for track in &gpx.tracks {
+            for segment in &track.segments {
+                let points = segment.iter().map(|p| p.point).collect();
+                self.shapes.push(Shape::LineString(points, style.track));
+            }
+        }
+        for route in &gpx.routes {
+            let points = route.points.iter().map(|p| p.point).collect();
+            self.shapes.push(Shape::LineString(points, style.route));
+        }
+        for waypoint in &gpx.waypoints {
+            self.markers.push(Marker {
+                position: waypoint.point,
+                label: waypoint.name.clone(),
+                ..style.waypoint.clone()
+            });
+        }
+        self
+    }
+
+    /// Moves the view so that all points of `gpx` are visible, see [`EMap::fit_bounds`].
+    #[cfg(feature = "gpx")]
+    pub fn fit_gpx(self, ctx: &Context, gpx: &Gpx, padding: f32) -> Self {
+        match gpx.bounding_rect() {
+            Some(bounds) => self.fit_bounds(ctx, bounds, padding),
+            None => self,
+        }
+    }
+
+    /// Draws the ground overlays and, on top of them, the placemarks of a KML document.
+    #[cfg(feature = "kml")]
+    pub fn kml(mut self, ctx: &Context, layer: &KmlLayer) -> Self {
+        self.image_overlays.extend(
+            layer
+                .ground_overlays()
+                .iter()
+                .filter_map(|o| o.overlay(ctx)),
+        );
+        for placemark in layer.placemarks() {
+            kml::push_shapes(
+                &placemark.geometry,
+                &placemark.style,
+                &mut self.shapes,
+                &mut self.markers,
+            );
+        }
+        self
+    }
+
     pub fn clear_state(self, ctx: &Context) -> Self {
         ctx.data_mut(|d| {
             d.remove::<EMapState>(self.id);
@@ -198,6 +705,76 @@ impl<'t> EMap<'t> {
         self
     }
 
+    /// The current view of the map, the default one if it hasn't been shown yet.
+    pub fn camera(&self, ctx: &Context) -> Camera {
+        EMapState::load(ctx, self.id)
+            .unwrap_or_else(EMapState::new)
+            .camera()
+    }
+
+    /// Moves the map to `camera`, with the zoom clamped to the supported range.
+    pub fn set_camera(self, ctx: &Context, camera: Camera) -> Self {
+        ctx.data_mut(|d| {
+            let s = d.get_temp_mut_or_insert_with::<EMapState>(self.id, EMapState::new);
+            s.set_camera(camera);
+            s.animation = None;
+        });
+        self
+    }
+
+    /// Moves the map to `camera` in a straight line, panning and zooming at the same time.
+    ///
+    /// ```no_run
+    /// use emap::{Camera, EMap, Easing, Transition};
+    ///
+    /// fn map_ui(ui: &mut egui::Ui) {
+    ///     let mut map = EMap::new("map");
+    ///     if ui.button("Zürich").clicked() {
+    ///         let zurich = Camera::new(geo::point!(x: 8.54, y: 47.37), 12.0);
+    ///         map = map.ease_to(ui.ctx(), zurich, Transition::new(0.5).easing(Easing::EaseOut));
+    ///     }
+    ///     map.show(ui);
+    /// }
+    /// ```
+    pub fn ease_to(self, ctx: &Context, camera: Camera, transition: Transition) -> Self {
+        self.animate(ctx, camera, transition, false)
+    }
+
+    /// Moves the map to `camera` by zooming out, panning and zooming in again, which keeps the
+    /// way there in view when the two are far apart.
+    pub fn fly_to(self, ctx: &Context, camera: Camera, transition: Transition) -> Self {
+        self.animate(ctx, camera, transition, true)
+    }
+
+    fn animate(self, ctx: &Context, camera: Camera, transition: Transition, fly: bool) -> Self {
+        let time = ctx.input(|i| i.time);
+        ctx.data_mut(|d| {
+            let s = d.get_temp_mut_or_insert_with::<EMapState>(self.id, EMapState::new);
+            s.animate(camera, transition, fly, time);
+        });
+        ctx.request_repaint();
+        self
+    }
+
+    /// Centers the longitude/latitude `bounds` and zooms in as far as they still fit into the
+    /// map, leaving `padding` screen points free on every side. Applied when the map is shown,
+    /// once its size is known.
+    pub fn fit_bounds(self, ctx: &Context, bounds: geo::Rect<f64>, padding: f32) -> Self {
+        ctx.data_mut(|d| {
+            let s = d.get_temp_mut_or_insert_with::<EMapState>(self.id, EMapState::new);
+            s.fit = Some((bounds, padding));
+        });
+        self
+    }
+
+    /// Moves the view so that all of `geometry` is visible, see [`EMap::fit_bounds`].
+    pub fn fit_geometry(self, ctx: &Context, geometry: &geo::Geometry<f64>, padding: f32) -> Self {
+        match geometry.bounding_rect() {
+            Some(bounds) => self.fit_bounds(ctx, bounds, padding),
+            None => self,
+        }
+    }
+
     pub fn show(mut self, ui: &mut Ui) -> EMapResponse {
         let mut state = EMapState::load(ui.ctx(), self.id).unwrap_or_else(EMapState::new);
 
@@ -217,18 +794,44 @@ impl<'t> EMap<'t> {
 
         let desired_tiles = major / pixel_tile_width;
 
-        let n_rect = norm_rect(state.x, state.y, state.zoom, desired_tiles);
+        if let Some((bounds, padding)) = state.fit.take() {
+            state.set_camera(Camera::fit_bounds(
+                bounds,
+                rect.size(),
+                padding,
+                self.tile_size,
+            ));
+            state.animation = None;
+        }
 
         let response = ui
             .interact(rect, self.id, Sense::click_and_drag())
             .on_hover_cursor(CursorIcon::Grab);
 
+        if let Some(animation) = state.animation {
+            let scrolled = response.hover_pos().is_some() && dy.abs() >= 0.01;
+            if scrolled || response.dragged() {
+                state.animation = None;
+            } else {
+                let time = ui.input(|i| i.time);
+                let (view, done) = animation.at(time, major, self.tile_size);
+                state.set_view(view);
+                if done {
+                    state.animation = None;
+                } else {
+                    ui.ctx().request_repaint();
+                }
+            }
+        }
+
+        let n_rect = norm_rect(state.x, state.y, state.zoom, desired_tiles);
+
         if let Some(pos) = response.hover_pos() {
             if dy.abs() >= 0.01 {
                 let pointer_norm = scale_rect(geo_from_pos2(pos), view_rect, n_rect);
 
                 state.zoom += (dy as f64) * 0.01;
-                state.zoom = state.zoom.clamp(0.75, 20.1);
+                state.zoom = state.zoom.clamp(camera::MIN_ZOOM, camera::MAX_ZOOM);
 
                 let n_rect = norm_rect(state.x, state.y, state.zoom, desired_tiles);
 
@@ -248,11 +851,6 @@ impl<'t> EMap<'t> {
             self.pointer_position = Some(pointer_merc);
         }
 
-        // let a_zoom = ui
-        //     .ctx()
-        //     .animate_value_with_time("zoom".into(), state.zoom as f32, 0.5);
-        // let n_rect = norm_rect(state.x, state.y, state.zoom, desired_tiles);
-
         ui.ctx().output_mut(|o| o.cursor_icon = CursorIcon::Grab);
 
         let east = n_rect.min().x;
@@ -265,34 +863,108 @@ impl<'t> EMap<'t> {
         let vy_min = view_rect.min().y;
         let vy_max = view_rect.max().y;
 
-        let tiles = TileId::from_bounds(
-            reverse_normalized_mercator(Point::new(east as f64, north as f64)),
-            reverse_normalized_mercator(Point::new(west as f64, south as f64)),
-            state.zoom as u8,
-            2,
-        );
+        let zoom = state.zoom as u8;
+
+        // the zoom the map is drawn at, before this frame's scrolling
+        let drawn_zoom = (desired_tiles / n_rect.width()).log2();
+        let projector = Projector::new(rect, view_rect, n_rect, drawn_zoom);
+        let to_screen = |p: Point<f64>| projector.normalized_to_screen(p);
+        let tile_rect = |tile: &TileId| {
+            Rect::from_min_max(
+                to_screen(tile.top_left_normalized()),
+                to_screen(tile.bottom_right_normalized()),
+            )
+        };
 
-        for tile in &tiles {
-            let top_left = tile.top_left_normalized();
-            let bottom_right = tile.bottom_right_normalized();
-            let r = Rect::from_min_max(
-                Pos2::new(
-                    scale(top_left.x(), east, west, vx_min, vx_max) as f32,
-                    scale(top_left.y(), south, north, vy_min, vy_max) as f32,
-                ),
-                Pos2::new(
-                    scale(bottom_right.x(), east, west, vx_min, vx_max) as f32,
-                    scale(bottom_right.y(), south, north, vy_min, vy_max) as f32,
-                ),
-            );
+        let visible_tiles = |zoom: u8, padding: i32| {
+            TileId::from_bounds(
+                reverse_normalized_mercator(Point::new(east, north)),
+                reverse_normalized_mercator(Point::new(west, south)),
+                zoom,
+                padding,
+            )
+        };
+
+        let mut used_textures = Vec::new();
+        let mut used_vector_tiles = Vec::new();
+        // painted with the other labels, on top of everything
+        let mut vector_labels = Vec::new();
+        for layer in &self.layers {
+            if !layer.is_active(zoom) {
+                continue;
+            }
+
+            match layer {
+                Layer::Tile(layer) => {
+                    let tint = Color32::WHITE.gamma_multiply(layer.options.opacity);
+
+                    let tiles = visible_tiles(layer.options.data_zoom(zoom, self.tile_size), 2);
+                    for tile in &tiles {
+                        let r = tile_rect(tile);
+
+                        let texture_handle =
+                            Self::find_texture_handle(layer, tile, &mut state, ui.ctx());
 
-            let texture_handle = self.find_texture_handle(tile, &mut state, ui.ctx());
+                        if let Some((url, texture_handle, uv)) = texture_handle {
+                            painter.image(texture_handle.id(), r, uv, tint);
+                            used_textures.push(url);
+                        }
+                    }
+                }
+                Layer::Vector(layer) => {
+                    if let Some(background) = &layer.style.background {
+                        let color = background
+                            .at(state.zoom)
+                            .gamma_multiply(layer.options.opacity);
+                        painter.rect_filled(rect, 0.0, color);
+                    }
 
-            if let Some((texture_handle, uv)) = texture_handle {
-                painter.image(texture_handle.id(), r, uv, Color32::WHITE);
+                    let tiles = visible_tiles(layer.options.data_zoom(zoom, self.tile_size), 1);
+
+                    for tile in &tiles {
+                        let Some((source, prepared)) = Self::find_vector_tile(
+                            layer,
+                            tile,
+                            &mut state,
+                            ui.ctx(),
+                            &mut used_vector_tiles,
+                        ) else {
+                            continue;
+                        };
+
+                        // a parent tile standing in for a missing one must not paint over its
+                        // loaded siblings
+                        let clip = tile_rect(tile).intersect(rect);
+                        let mut tile_labels = Vec::new();
+                        prepared.paint(
+                            &painter.with_clip_rect(clip),
+                            layer.style,
+                            state.zoom,
+                            layer.options.opacity,
+                            |p| to_screen(source.relative_to_normalized(p)),
+                            &mut tile_labels,
+                        );
+                        vector_labels
+                            .extend(tile_labels.into_iter().filter(|l| clip.contains(l.pos)));
+                    }
+                }
             }
         }
-        state.unload_unused_textures(&tiles);
+        state.unload_unused_textures(&used_textures);
+        state.unload_unused_vector_tiles(&used_vector_tiles);
+
+        self.run_painters(PaintOrder::AboveTiles, &painter, &projector);
+
+        for overlay in &self.image_overlays {
+            painter.add(overlay.mesh(|p| to_screen(normalized_mercator(p))));
+        }
+
+        for heatmap in &self.heatmaps {
+            heatmap.paint(&painter, ui.ctx(), &projector);
+        }
+
+        // labels of markers and the text labels, painted on top of everything else
+        let mut labels = Vec::new();
 
         for shape in &self.shapes {
             match shape {
@@ -325,6 +997,14 @@ impl<'t> EMap<'t> {
 
                     painter.line(points, *stroke);
                 }
+                Shape::StyledLine(points, style) => {
+                    let points = points
+                        .iter()
+                        .map(|p| to_screen(normalized_mercator(*p)))
+                        .collect::<Vec<_>>();
+
+                    style.paint(&painter, &points);
+                }
                 Shape::Circle(point, radius, stroke, fill) => {
                     let center = normalized_mercator(*point);
                     let center = Pos2::new(
@@ -346,9 +1026,95 @@ impl<'t> EMap<'t> {
                         painter.circle_stroke(center, radius, stroke);
                     }
                 }
+                // painted with the other markers
+                Shape::Marker(_) => {}
+                Shape::Polygon(polygons, stroke, fill) => {
+                    // triangulate in projected space, where the edges are straight lines
+                    let polygons = polygons.map_coords(|c| normalized_mercator(c.into()).into());
+
+                    if let Some(fill) = fill {
+                        let mut mesh = egui::Mesh::default();
+                        for polygon in &polygons {
+                            Triangulation::from_polygon(polygon)
+                                .add_to_mesh(&mut mesh, *fill, to_screen);
+                        }
+                        painter.add(mesh);
+                    }
+
+                    if let Some(stroke) = stroke {
+                        for polygon in &polygons {
+                            for ring in
+                                std::iter::once(polygon.exterior()).chain(polygon.interiors())
+                            {
+                                let points = ring.points().map(to_screen).collect::<Vec<_>>();
+                                painter.line(points, *stroke);
+                            }
+                        }
+                    }
+                }
             }
         }
 
+        self.run_painters(PaintOrder::AboveShapes, &painter, &projector);
+
+        let bubbles = self
+            .clusters
+            .iter()
+            .flat_map(|c| c.paint(&painter, zoom, self.tile_size, to_screen))
+            .collect::<Vec<_>>();
+
+        // sorted by reference, `Shape::Marker` refers to markers by their index
+        let mut markers = self.markers.iter().collect::<Vec<_>>();
+        markers.sort_by_key(|m| m.z_index);
+        for marker in markers {
+            let pos = to_screen(normalized_mercator(marker.position));
+            marker.paint(&painter, pos);
+            labels.extend(marker.label_at(pos));
+        }
+        labels.extend(
+            self.labels
+                .iter()
+                .map(|l| l.at(to_screen(normalized_mercator(l.position)))),
+        );
+        // after the labels added to the map, which win among equal priorities
+        labels.extend(vector_labels);
+        label::paint_labels(&painter, labels, self.label_collisions);
+
+        self.run_painters(PaintOrder::Top, &painter, &projector);
+
+        #[cfg(feature = "geojson")]
+        let clicked_feature = response
+            .interact_pointer_pos()
+            .filter(|_| response.clicked())
+            .and_then(|pos| {
+                let project = |p| to_screen(normalized_mercator(p));
+                self.feature_shapes
+                    .iter()
+                    .rev()
+                    .find(|(range, _)| {
+                        self.shapes[range.clone()]
+                            .iter()
+                            .any(|s| s.hit(pos, 3.0, &self.markers, project))
+                    })
+                    .map(|(_, feature)| *feature)
+            });
+
+        // the attribution hugs its corner, legends in the same corner are stacked on top of it
+        let mut panels = CornerPanels::new(rect);
+        if self.show_attribution {
+            let attributions = attribution::merge_attributions(
+                self.layers
+                    .iter()
+                    .filter(|l| l.is_active(zoom))
+                    .flat_map(|l| l.provider().attribution())
+                    .chain(self.attributions.iter().cloned()),
+            );
+            attribution::show_attribution(ui, &mut panels, self.attribution_anchor, &attributions);
+        }
+        for legend in &self.legends {
+            legend.show(ui, &mut panels);
+        }
+
         let drag = response.drag_delta();
         if drag != Vec2::ZERO {
             // input range x 0.0 .. w
@@ -365,42 +1131,100 @@ impl<'t> EMap<'t> {
             state.y = state.y.clamp(0.0, 1.0);
         }
 
+        if response.clicked()
+            && let Some(pos) = response.interact_pointer_pos()
+            && let Some(bubble) = bubbles.iter().rev().find(|b| b.contains(pos))
+        {
+            let mut target = Camera::fit_bounds(bubble.bounds, rect.size(), 0.0, self.tile_size);
+            // points at the same spot only come apart above the clustered zooms
+            target.zoom = target.zoom.max(zoom as f64 + 1.0);
+            let time = ui.input(|i| i.time);
+            state.animate(target, Transition::new(0.4), false, time);
+            ui.ctx().request_repaint();
+        }
+
+        let n_rect = norm_rect(state.x, state.y, state.zoom, desired_tiles);
+        let from_screen =
+            |pos| reverse_normalized_mercator(scale_rect(geo_from_pos2(pos), view_rect, n_rect));
+        let visible_bounds = geo::Rect::new(from_screen(rect.min), from_screen(rect.max));
+        let camera = state.camera();
+
         state.store(ui.ctx(), self.id);
 
         EMapResponse {
             response,
             pointer_position: self.pointer_position,
+            camera,
+            visible_bounds,
+            projector,
+            #[cfg(feature = "geojson")]
+            clicked_feature,
         }
     }
 
+    /// Finds the prepared vector tile for `tile`, or the closest prepared parent tile while it is
+    /// still being fetched or decoded. The urls of the tiles it keeps are added to `used`.
+    fn find_vector_tile(
+        layer: &VectorTileLayer<'_>,
+        tile: &TileId,
+        state: &mut EMapState,
+        ctx: &Context,
+        used: &mut Vec<String>,
+    ) -> Option<(TileId, Arc<PreparedTile>)> {
+        let url = layer.provider.url(*tile);
+        if !state.vector_tiles.contains_key(&url)
+            && let Some(loading) = PreparedTile::load(layer, url.clone(), tile, ctx)
+        {
+            state.vector_tiles.insert(url.clone(), loading);
+        }
+
+        if let Some(loading) = state.vector_tiles.get(&url) {
+            used.push(url);
+            if let Some(prepared) = loading.get() {
+                return Some((*tile, prepared.clone()));
+            }
+        }
+
+        let mut parent = *tile;
+        while parent.z > 0 {
+            (parent, _) = parent.zoom_out_with_uv(Rect::ZERO);
+            let url = layer.provider.url(parent);
+            if let Some(prepared) = state.vector_tiles.get(&url).and_then(|l| l.get()) {
+                let prepared = prepared.clone();
+                used.push(url);
+                return Some((parent, prepared));
+            }
+        }
+
+        None
+    }
+
+    /// Finds the texture of `tile`, or the part of the closest loaded parent tile covering it,
+    /// along with the url of the texture.
     fn find_texture_handle(
-        &self,
+        layer: &TileLayer<'_>,
         tile: &TileId,
         state: &mut EMapState,
         ctx: &Context,
-    ) -> Option<(TextureHandle, Rect)> {
-        let texture_handle = state.registered_tile_textures.get(tile).cloned();
+    ) -> Option<(String, TextureHandle, Rect)> {
+        let url = layer.provider.url(*tile);
+
+        let texture_handle = state.registered_tile_textures.get(&url).cloned();
         if let Some(h) = texture_handle {
             let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
-            return Some((h, uv));
+            return Some((url, h, uv));
         }
 
-        let url = self.tile_url_provider.url(*tile).to_string();
-
-        let loader: &dyn TileLoader = self
-            .tile_loader
-            .unwrap_or_else(|| DEFAULT_TILE_LOADER.deref());
+        let loader: &dyn TileLoader = layer.loader.unwrap_or_else(|| DEFAULT_TILE_LOADER.deref());
 
-        let img_data = loader.tile(url, tile, ctx.clone());
+        let img_data = loader.tile(url.clone(), tile, ctx.clone());
         if let Some(img_data) = img_data {
-            let h = ctx.load_texture(
-                format!("{:?}", tile),
-                img_data,
-                egui::TextureOptions::LINEAR,
-            );
-            state.registered_tile_textures.insert(*tile, h.clone());
+            let h = ctx.load_texture(url.clone(), img_data, egui::TextureOptions::LINEAR);
+            state
+                .registered_tile_textures
+                .insert(url.clone(), h.clone());
             let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
-            return Some((h, uv));
+            return Some((url, h, uv));
         }
 
         let (mut new_tile, mut new_uv) =
@@ -410,9 +1234,10 @@ impl<'t> EMap<'t> {
                 break;
             }
 
-            let texture_handle = state.registered_tile_textures.get(&new_tile).cloned();
+            let url = layer.provider.url(new_tile);
+            let texture_handle = state.registered_tile_textures.get(&url).cloned();
             if let Some(h) = texture_handle {
-                return Some((h, new_uv));
+                return Some((url, h, new_uv));
             }
             (new_tile, new_uv) = new_tile.zoom_out_with_uv(new_uv);
         }
@@ -583,6 +1408,13 @@ impl TileId {
         Point::new(x_tile, y_tile)
     }
 
+    /// Maps a position relative to this tile, as used by vector tiles, to normalized mercator.
+    fn relative_to_normalized(&self, p: Point<f64>) -> Point<f64> {
+        let n = 2.0f64.powi(self.z as i32);
+
+        Point::new((self.x as f64 + p.x()) / n, (self.y as f64 + p.y()) / n)
+    }
+
     fn zoom_out_with_uv(&self, uv: Rect) -> (TileId, Rect) {
         let new_tile = TileId {
             x: self.x / 2,
@@ -607,3 +1439,41 @@ impl TileId {
         (new_tile, uv)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn style_markers_are_drawn_above_shapes() {
+        let ctx = Context::default();
+        let texture = egui::TextureId::User(7);
+        let style = GeometryStyle::new().marker(Marker::texture(
+            Point::new(0.0, 0.0),
+            texture,
+            egui::Vec2::splat(16.0),
+        ));
+        let square = geo::Rect::new((-10.0, -10.0), (10.0, 10.0)).to_polygon();
+
+        let output = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                EMap::new("map")
+                    .base_layer(TileLayer::new(&OsmStandardTileUrlProvider).visible(false))
+                    .initial_position(ctx, 0.0, 0.0, 3)
+                    .geometry(Point::new(0.0, 0.0), style.clone())
+                    .filled_polygon(square.clone(), Color32::RED)
+                    .show(ui);
+            });
+        });
+
+        let mesh_position = |found: &dyn Fn(&egui::Mesh) -> bool| {
+            output.shapes.iter().position(|s| match &s.shape {
+                egui::Shape::Mesh(mesh) => found(mesh),
+                _ => false,
+            })
+        };
+        let marker = mesh_position(&|m| m.texture_id == texture).unwrap();
+        let polygon = mesh_position(&|m| m.vertices.iter().any(|v| v.color == Color32::RED));
+        assert!(polygon.unwrap() < marker);
+    }
+}
@@ -0,0 +1,339 @@
+use egui::{Color32, Painter, Pos2, Rect, Stroke, Vec2, epaint::PathShape};
+
+/// How the line itself is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinePattern {
+    Solid,
+    /// Dashes of `dash` screen points separated by gaps of `gap` screen points.
+    Dashed {
+        dash: f32,
+        gap: f32,
+    },
+    /// Round dots of the stroke width, `spacing` screen points apart.
+    Dotted {
+        spacing: f32,
+    },
+}
+
+/// The ends of lines and dashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineCap {
+    /// Ends exactly at the end point.
+    Butt,
+    /// Ends in a half circle around the end point.
+    Round,
+    /// Extends half the stroke width beyond the end point.
+    Square,
+}
+
+/// The corners of lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+}
+
+/// Arrows along a line pointing in its direction, e.g. to show the direction of a route or flow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineArrows {
+    /// Distance between arrows in screen points.
+    pub spacing: f32,
+    /// Length and width of an arrow in screen points.
+    pub size: f32,
+    pub color: Color32,
+}
+
+/// A line style beyond a plain [`Stroke`], see [`crate::EMap::styled_line_string`].
+///
+/// ```no_run
+/// use egui::{Color32, Stroke};
+/// use emap::{EMap, LineCap, LineStyle};
+///
+/// let route = vec![geo::point!(x: 8.54, y: 47.37), geo::point!(x: 8.60, y: 47.40)];
+/// let style = LineStyle::new(Stroke::new(4.0, Color32::from_rgb(51, 136, 255)))
+///     .casing(Stroke::new(1.5, Color32::WHITE))
+///     .dashed(12.0, 6.0)
+///     .cap(LineCap::Round)
+///     .arrows(80.0, 10.0, Color32::WHITE);
+/// let map = EMap::new("map").styled_line_string(route, style);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineStyle {
+    pub(crate) stroke: Stroke,
+    pub(crate) pattern: LinePattern,
+    pub(crate) casing: Option<Stroke>,
+    pub(crate) cap: LineCap,
+    pub(crate) join: LineJoin,
+    pub(crate) arrows: Option<LineArrows>,
+}
+
+impl LineStyle {
+    pub fn new(stroke: Stroke) -> Self {
+        Self {
+            stroke,
+            pattern: LinePattern::Solid,
+            casing: None,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            arrows: None,
+        }
+    }
+
+    pub fn pattern(mut self, pattern: LinePattern) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    pub fn dashed(self, dash: f32, gap: f32) -> Self {
+        self.pattern(LinePattern::Dashed { dash, gap })
+    }
+
+    pub fn dotted(self, spacing: f32) -> Self {
+        self.pattern(LinePattern::Dotted { spacing })
+    }
+
+    /// An outline of `casing.width` on both sides of the line, drawn solid below it.
+    pub fn casing(mut self, casing: impl Into<Option<Stroke>>) -> Self {
+        self.casing = casing.into();
+        self
+    }
+
+    pub fn cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn arrows(mut self, spacing: f32, size: f32, color: Color32) -> Self {
+        self.arrows = Some(LineArrows {
+            spacing,
+            size,
+            color,
+        });
+        self
+    }
+
+    /// The width of the line including its casing.
+    pub(crate) fn width(&self) -> f32 {
+        self.stroke.width + 2.0 * self.casing.map_or(0.0, |c| c.width)
+    }
+
+    pub(crate) fn paint(&self, painter: &Painter, points: &[Pos2]) {
+        if points.len() < 2 {
+            return;
+        }
+
+        if let Some(casing) = self.casing {
+            self.paint_solid(painter, points, Stroke::new(self.width(), casing.color));
+        }
+
+        // dashes, dots and arrows are only generated where they can be seen, a long line can be
+        // millions of screen points long when zoomed in
+        let margin = self.width().max(self.arrows.map_or(0.0, |a| a.size)) + 1.0;
+        let visible = visible_pieces(points, painter.clip_rect().expand(margin));
+
+        match self.pattern {
+            LinePattern::Solid => self.paint_solid(painter, points, self.stroke),
+            LinePattern::Dashed { dash, gap } => {
+                let (dash, gap) = (dash.max(0.1), gap.max(0.0));
+                for (start, piece) in &visible {
+                    for dash in dashes(piece, dash, gap, start % (dash + gap)) {
+                        self.paint_solid(painter, &dash, self.stroke);
+                    }
+                }
+            }
+            LinePattern::Dotted { spacing } => {
+                let spacing = spacing.max(0.1);
+                for (start, piece) in &visible {
+                    for (pos, _) in along(piece, first_after(0.0, *start, spacing), spacing) {
+                        painter.circle_filled(pos, self.stroke.width / 2.0, self.stroke.color);
+                    }
+                }
+            }
+        }
+
+        if let Some(arrows) = self.arrows {
+            let spacing = arrows.spacing.max(arrows.size).max(0.1);
+            for (start, piece) in &visible {
+                let offset = first_after(spacing / 2.0, *start, spacing);
+                for (pos, dir) in along(piece, offset, spacing) {
+                    let back = pos - dir * arrows.size / 2.0;
+                    let side = dir.rot90() * arrows.size / 2.0;
+                    painter.add(PathShape::convex_polygon(
+                        vec![pos + dir * arrows.size / 2.0, back + side, back - side],
+                        arrows.color,
+                        Stroke::NONE,
+                    ));
+                }
+            }
+        }
+    }
+
+    fn paint_solid(&self, painter: &Painter, points: &[Pos2], stroke: Stroke) {
+        let mut points = points.to_vec();
+        let radius = stroke.width / 2.0;
+
+        if self.cap == LineCap::Square {
+            let n = points.len();
+            let start = (points[0] - points[1]).normalized();
+            let end = (points[n - 1] - points[n - 2]).normalized();
+            points[0] += start * radius;
+            points[n - 1] += end * radius;
+        }
+
+        if self.join == LineJoin::Round {
+            for p in &points[1..points.len() - 1] {
+                painter.circle_filled(*p, radius, stroke.color);
+            }
+        }
+        if self.cap == LineCap::Round {
+            painter.circle_filled(points[0], radius, stroke.color);
+            painter.circle_filled(points[points.len() - 1], radius, stroke.color);
+        }
+
+        painter.line(points, stroke);
+    }
+}
+
+impl From<Stroke> for LineStyle {
+    fn from(stroke: Stroke) -> Self {
+        Self::new(stroke)
+    }
+}
+
+/// The parts of the polyline within `clip`, with the distance along the line at which they
+/// start.
+fn visible_pieces(points: &[Pos2], clip: Rect) -> Vec<(f32, Vec<Pos2>)> {
+    let mut pieces: Vec<(f32, Vec<Pos2>)> = Vec::new();
+    let mut open = false;
+    let mut travelled = 0.0;
+
+    for pair in points.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let length = a.distance(b);
+
+        match clip_segment(a, b, clip) {
+            Some((t0, t1)) => {
+                let (p0, p1) = (a + (b - a) * t0, a + (b - a) * t1);
+                if open && t0 == 0.0 {
+                    if let Some((_, piece)) = pieces.last_mut() {
+                        piece.push(p1);
+                    }
+                } else {
+                    pieces.push((travelled + length * t0, vec![p0, p1]));
+                }
+                open = t1 == 1.0;
+            }
+            None => open = false,
+        }
+        travelled += length;
+    }
+
+    pieces
+}
+
+/// The part `t0..t1` of the segment from `a` to `b` within `clip` (Liang-Barsky).
+fn clip_segment(a: Pos2, b: Pos2, clip: Rect) -> Option<(f32, f32)> {
+    let d = b - a;
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for (p, q) in [
+        (-d.x, a.x - clip.min.x),
+        (d.x, clip.max.x - a.x),
+        (-d.y, a.y - clip.min.y),
+        (d.y, clip.max.y - a.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+    (t0 <= t1).then_some((t0, t1))
+}
+
+/// Where the first of the positions `first, first + spacing, ...` along a line lies on a piece of
+/// it starting at `start`, relative to that piece.
+fn first_after(first: f32, start: f32, spacing: f32) -> f32 {
+    if start <= first {
+        first - start
+    } else {
+        (first - start).rem_euclid(spacing)
+    }
+}
+
+/// Cuts the polyline into dashes, `phase` screen points into the dash pattern.
+fn dashes(points: &[Pos2], dash: f32, gap: f32, phase: f32) -> Vec<Vec<Pos2>> {
+    let mut dashes = Vec::new();
+    let mut drawing = phase < dash;
+    let mut current = if drawing { vec![points[0]] } else { Vec::new() };
+    let mut left = if drawing {
+        dash - phase
+    } else {
+        dash + gap - phase
+    };
+
+    for pair in points.windows(2) {
+        let (mut a, b) = (pair[0], pair[1]);
+        let mut length = a.distance(b);
+        if length <= 0.0 {
+            continue;
+        }
+        while length >= left {
+            a += (b - a) * (left / length);
+            length -= left;
+            if drawing {
+                current.push(a);
+                dashes.push(std::mem::take(&mut current));
+                left = gap;
+            } else {
+                current.push(a);
+                left = dash;
+            }
+            drawing = !drawing;
+        }
+        left -= length;
+        if drawing {
+            current.push(b);
+        }
+    }
+    if drawing && current.len() > 1 {
+        dashes.push(current);
+    }
+
+    dashes
+}
+
+/// Positions every `spacing` screen points along the polyline, starting at `offset`, with the
+/// direction of the line there.
+fn along(points: &[Pos2], offset: f32, spacing: f32) -> Vec<(Pos2, Vec2)> {
+    let mut positions = Vec::new();
+    let mut next = offset;
+    let mut travelled = 0.0;
+
+    for pair in points.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let length = a.distance(b);
+        if length <= 0.0 {
+            continue;
+        }
+        let dir = (b - a) / length;
+        while next <= travelled + length {
+            positions.push((a + dir * (next - travelled), dir));
+            next += spacing;
+        }
+        travelled += length;
+    }
+
+    positions
+}
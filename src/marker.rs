@@ -0,0 +1,212 @@
+use egui::{
+    Align2, Color32, FontId, Mesh, Painter, Pos2, Rect, Stroke, TextureId, Vec2, emath::Rot2,
+    epaint::PathShape,
+};
+use geo::Point;
+
+use crate::label::Label;
+
+/// What a [`Marker`] looks like.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarkerIcon {
+    /// The classic map pin with its tip at the marker position.
+    Pin { fill: Color32, stroke: Stroke },
+    /// An arrow pointing up, to be turned with [`Marker::rotation`], e.g. into a heading.
+    Arrow { fill: Color32, stroke: Stroke },
+    /// A texture, e.g. loaded with [`egui::Context::load_texture`], tinted with `tint`.
+    Texture { texture: TextureId, tint: Color32 },
+}
+
+/// A marker on the map, sized in screen points and therefore the same size at every zoom.
+/// Markers are drawn on top of all shapes, in the order of their [`Marker::z_index`].
+///
+/// ```no_run
+/// use egui::Vec2;
+/// use emap::{EMap, Marker};
+///
+/// let map = EMap::new("map")
+///     .marker(Marker::new(geo::point!(x: 8.54, y: 47.37)).label("Zürich"))
+///     .marker(
+///         Marker::arrow(geo::point!(x: 8.55, y: 47.36))
+///             .rotation(135.0)
+///             .z_index(1),
+///     );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    pub(crate) position: Point<f64>,
+    pub(crate) icon: MarkerIcon,
+    pub(crate) size: Vec2,
+    pub(crate) anchor: Vec2,
+    pub(crate) rotation: f32,
+    pub(crate) z_index: i32,
+
+    pub(crate) label: Option<String>,
+    pub(crate) label_size: f32,
+    pub(crate) label_color: Color32,
+    pub(crate) label_halo: Option<Stroke>,
+}
+
+impl Marker {
+    /// A red pin pointing at `position`.
+    pub fn new(position: Point<f64>) -> Self {
+        Self {
+            position,
+            icon: MarkerIcon::Pin {
+                fill: Color32::from_rgb(230, 40, 40),
+                stroke: Stroke::new(1.0, Color32::from_rgb(120, 20, 20)),
+            },
+            size: Vec2::new(24.0, 36.0),
+            anchor: Vec2::new(0.5, 1.0),
+            rotation: 0.0,
+            z_index: 0,
+
+            label: None,
+            label_size: 14.0,
+            label_color: Color32::BLACK,
+            label_halo: Some(Stroke::new(1.5, Color32::WHITE)),
+        }
+    }
+
+    /// A blue arrow centered on `position`.
+    pub fn arrow(position: Point<f64>) -> Self {
+        Self::new(position)
+            .icon(MarkerIcon::Arrow {
+                fill: Color32::from_rgb(51, 136, 255),
+                stroke: Stroke::new(1.5, Color32::WHITE),
+            })
+            .size(Vec2::splat(24.0))
+            .anchor(Vec2::splat(0.5))
+    }
+
+    /// A texture of `size` screen points centered on `position`.
+    pub fn texture(position: Point<f64>, texture: TextureId, size: Vec2) -> Self {
+        Self::new(position)
+            .icon(MarkerIcon::Texture {
+                texture,
+                tint: Color32::WHITE,
+            })
+            .size(size)
+            .anchor(Vec2::splat(0.5))
+    }
+
+    pub fn icon(mut self, icon: MarkerIcon) -> Self {
+        self.icon = icon;
+        self
+    }
+
+    /// The size of the icon in screen points.
+    pub fn size(mut self, size: Vec2) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// The point of the icon placed at the marker position, as a fraction of its size: `(0, 0)`
+    /// is the top left and `(1, 1)` the bottom right corner.
+    pub fn anchor(mut self, anchor: Vec2) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Turns the icon clockwise around its anchor, in degrees.
+    pub fn rotation(mut self, degrees: f32) -> Self {
+        self.rotation = degrees;
+        self
+    }
+
+    /// Markers with a higher z-index are drawn on top of those with a lower one, markers with
+    /// the same z-index in the order they were added.
+    pub fn z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Text shown below the icon. It is hidden like a [`crate::TextLabel`] with the z-index as
+    /// priority when it overlaps other labels.
+    pub fn label(mut self, text: impl Into<String>) -> Self {
+        self.label = Some(text.into());
+        self
+    }
+
+    pub fn label_style(mut self, size: f32, color: Color32, halo: Option<Stroke>) -> Self {
+        self.label_size = size;
+        self.label_color = color;
+        self.label_halo = halo;
+        self
+    }
+
+    /// The screen area of the unrotated icon with its anchor at `pos`.
+    pub(crate) fn rect(&self, pos: Pos2) -> Rect {
+        Rect::from_min_size(pos - self.anchor * self.size, self.size)
+    }
+
+    /// Paints the icon with its anchor at `pos`.
+    pub(crate) fn paint(&self, painter: &Painter, pos: Pos2) {
+        let rect = self.rect(pos);
+        let rot = Rot2::from_angle(self.rotation.to_radians());
+        let turn = |p: Pos2| pos + rot * (p - pos);
+
+        match self.icon {
+            MarkerIcon::Pin { fill, stroke } => {
+                let r = rect.width() / 2.0;
+                let center = Pos2::new(rect.center().x, rect.top() + r);
+                let tip = Pos2::new(rect.center().x, rect.bottom());
+
+                // the outline runs along the tangents from the tip to the head, which keeps it
+                // convex
+                let d = (tip.y - center.y).max(r);
+                let a = (r / d).acos();
+                let down = std::f32::consts::FRAC_PI_2;
+                let mut points = vec![turn(tip)];
+                for i in 0..=24 {
+                    let angle = down + a + i as f32 / 24.0 * (std::f32::consts::TAU - 2.0 * a);
+                    points.push(turn(center + Vec2::angled(angle) * r));
+                }
+                painter.add(PathShape::convex_polygon(points, fill, stroke));
+                painter.circle_filled(turn(center), r * 0.4, stroke.color);
+            }
+            MarkerIcon::Arrow { fill, stroke } => {
+                let points = [
+                    rect.center_top(),
+                    rect.right_bottom(),
+                    Pos2::new(rect.center().x, rect.top() + rect.height() * 0.7),
+                    rect.left_bottom(),
+                ]
+                .map(turn);
+
+                let mut mesh = Mesh::default();
+                for p in points {
+                    mesh.colored_vertex(p, fill);
+                }
+                mesh.add_triangle(0, 1, 2);
+                mesh.add_triangle(0, 2, 3);
+                painter.add(mesh);
+                painter.add(PathShape::closed_line(points.to_vec(), stroke));
+            }
+            MarkerIcon::Texture { texture, tint } => {
+                let mut mesh = Mesh::with_texture(texture);
+                mesh.add_rect_with_uv(
+                    rect,
+                    Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+                    tint,
+                );
+                mesh.rotate(rot, pos);
+                painter.add(mesh);
+            }
+        }
+    }
+
+    /// The label of the marker placed at `pos`, centered below the icon.
+    pub(crate) fn label_at(&self, pos: Pos2) -> Option<Label> {
+        let below = (1.0 - self.anchor.y) * self.size.y;
+        Some(Label {
+            pos: pos + Vec2::new(0.0, below + 2.0),
+            text: self.label.clone()?,
+            font: FontId::proportional(self.label_size),
+            color: self.label_color,
+            halo: self.label_halo,
+            anchor: Align2::CENTER_TOP,
+            priority: self.z_index,
+        })
+    }
+}
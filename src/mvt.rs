@@ -0,0 +1,661 @@
+//! Decoder for [Mapbox Vector Tiles](https://github.com/mapbox/vector-tile-spec/tree/master/2.1).
+
+use std::collections::HashMap;
+
+use geo::Point;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MvtError {
+    UnexpectedEof,
+    UnsupportedWireType(u8),
+    InvalidUtf8,
+    InvalidGeometry,
+    /// A layer extent of zero, or one too large for a `u32`.
+    InvalidExtent,
+}
+
+impl std::fmt::Display for MvtError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MvtError::UnexpectedEof => write!(f, "unexpected end of vector tile data"),
+            MvtError::UnsupportedWireType(t) => write!(f, "unsupported protobuf wire type {t}"),
+            MvtError::InvalidUtf8 => write!(f, "vector tile string is not valid utf-8"),
+            MvtError::InvalidGeometry => write!(f, "invalid vector tile geometry"),
+            MvtError::InvalidExtent => write!(f, "invalid vector tile layer extent"),
+        }
+    }
+}
+
+impl std::error::Error for MvtError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MvtValue {
+    String(String),
+    Float(f32),
+    Double(f64),
+    Int(i64),
+    UInt(u64),
+    SInt(i64),
+    Bool(bool),
+}
+
+impl MvtValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MvtValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            MvtValue::Float(v) => Some(*v as f64),
+            MvtValue::Double(v) => Some(*v),
+            MvtValue::Int(v) | MvtValue::SInt(v) => Some(*v as f64),
+            MvtValue::UInt(v) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            MvtValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for MvtValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MvtValue::String(s) => write!(f, "{s}"),
+            MvtValue::Float(v) => write!(f, "{v}"),
+            MvtValue::Double(v) => write!(f, "{v}"),
+            MvtValue::Int(v) | MvtValue::SInt(v) => write!(f, "{v}"),
+            MvtValue::UInt(v) => write!(f, "{v}"),
+            MvtValue::Bool(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+/// Feature geometry with coordinates relative to the tile, `(0, 0)` is the top left and `(1, 1)`
+/// the bottom right corner. Coordinates in the tile buffer fall outside of that range.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MvtGeometry {
+    Points(Vec<Point<f64>>),
+    LineStrings(Vec<Vec<Point<f64>>>),
+    /// Each polygon is a list of rings, the exterior ring followed by its holes.
+    Polygons(Vec<Vec<Vec<Point<f64>>>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MvtFeature {
+    pub id: Option<u64>,
+    pub properties: HashMap<String, MvtValue>,
+    pub geometry: MvtGeometry,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MvtLayer {
+    pub name: String,
+    pub extent: u32,
+    pub features: Vec<MvtFeature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct VectorTile {
+    pub layers: Vec<MvtLayer>,
+}
+
+impl VectorTile {
+    pub fn decode(data: &[u8]) -> Result<Self, MvtError> {
+        let mut layers = Vec::new();
+
+        let mut r = Reader::new(data);
+        while let Some((field, wire_type)) = r.key()? {
+            match field {
+                3 => layers.push(decode_layer(r.bytes()?)?),
+                _ => r.skip(wire_type)?,
+            }
+        }
+
+        Ok(Self { layers })
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&MvtLayer> {
+        self.layers.iter().find(|l| l.name == name)
+    }
+}
+
+const GEOM_POINT: u64 = 1;
+const GEOM_LINESTRING: u64 = 2;
+const GEOM_POLYGON: u64 = 3;
+
+fn decode_layer(data: &[u8]) -> Result<MvtLayer, MvtError> {
+    let mut name = String::new();
+    let mut extent = 4096;
+    let mut keys = Vec::new();
+    let mut values = Vec::new();
+    let mut raw_features = Vec::new();
+
+    let mut r = Reader::new(data);
+    while let Some((field, wire_type)) = r.key()? {
+        match field {
+            1 => name = r.string()?,
+            2 => raw_features.push(r.bytes()?),
+            3 => keys.push(r.string()?),
+            4 => values.push(decode_value(r.bytes()?)?),
+            5 => {
+                extent = u32::try_from(r.varint()?)
+                    .ok()
+                    .filter(|e| *e != 0)
+                    .ok_or(MvtError::InvalidExtent)?
+            }
+            _ => r.skip(wire_type)?,
+        }
+    }
+
+    let features = raw_features
+        .into_iter()
+        .map(|f| decode_feature(f, &keys, &values, extent))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(MvtLayer {
+        name,
+        extent,
+        features,
+    })
+}
+
+fn decode_value(data: &[u8]) -> Result<MvtValue, MvtError> {
+    let mut value = MvtValue::Bool(false);
+
+    let mut r = Reader::new(data);
+    while let Some((field, wire_type)) = r.key()? {
+        value = match field {
+            1 => MvtValue::String(r.string()?),
+            2 => MvtValue::Float(f32::from_bits(r.fixed32()?)),
+            3 => MvtValue::Double(f64::from_bits(r.fixed64()?)),
+            4 => MvtValue::Int(r.varint()? as i64),
+            5 => MvtValue::UInt(r.varint()?),
+            6 => MvtValue::SInt(zigzag(r.varint()?)),
+            7 => MvtValue::Bool(r.varint()? != 0),
+            _ => {
+                r.skip(wire_type)?;
+                continue;
+            }
+        };
+    }
+
+    Ok(value)
+}
+
+fn decode_feature(
+    data: &[u8],
+    keys: &[String],
+    values: &[MvtValue],
+    extent: u32,
+) -> Result<MvtFeature, MvtError> {
+    let mut id = None;
+    let mut tags = Vec::new();
+    let mut geom_type = 0;
+    let mut commands = Vec::new();
+
+    let mut r = Reader::new(data);
+    while let Some((field, wire_type)) = r.key()? {
+        match field {
+            1 => id = Some(r.varint()?),
+            2 => tags = r.packed_varints()?,
+            3 => geom_type = r.varint()?,
+            4 => commands = r.packed_varints()?,
+            _ => r.skip(wire_type)?,
+        }
+    }
+
+    let mut properties = HashMap::new();
+    for tag in tags.chunks_exact(2) {
+        let key = keys.get(tag[0] as usize);
+        let value = values.get(tag[1] as usize);
+        if let (Some(key), Some(value)) = (key, value) {
+            properties.insert(key.clone(), value.clone());
+        }
+    }
+
+    let rings = decode_geometry(&commands, extent)?;
+    let geometry = match geom_type {
+        GEOM_POINT => MvtGeometry::Points(rings.into_iter().flatten().collect()),
+        GEOM_LINESTRING => MvtGeometry::LineStrings(rings),
+        GEOM_POLYGON => MvtGeometry::Polygons(group_rings(rings)),
+        // features of unknown type may be ignored according to the spec
+        _ => MvtGeometry::Points(Vec::new()),
+    };
+
+    Ok(MvtFeature {
+        id,
+        properties,
+        geometry,
+    })
+}
+
+/// Runs the geometry command stream, returning one point list per `MoveTo`.
+fn decode_geometry(commands: &[u64], extent: u32) -> Result<Vec<Vec<Point<f64>>>, MvtError> {
+    const MOVE_TO: u64 = 1;
+    const LINE_TO: u64 = 2;
+    const CLOSE_PATH: u64 = 7;
+
+    let extent = extent as f64;
+
+    let mut parts: Vec<Vec<Point<f64>>> = Vec::new();
+    let mut cursor = (0i64, 0i64);
+
+    let mut i = 0;
+    while i < commands.len() {
+        let command = commands[i] & 0x7;
+        let count = (commands[i] >> 3) as usize;
+        i += 1;
+
+        match command {
+            MOVE_TO | LINE_TO => {
+                if count
+                    .checked_mul(2)
+                    .and_then(|n| n.checked_add(i))
+                    .is_none_or(|end| end > commands.len())
+                {
+                    return Err(MvtError::InvalidGeometry);
+                }
+
+                for _ in 0..count {
+                    cursor = cursor
+                        .0
+                        .checked_add(zigzag(commands[i]))
+                        .zip(cursor.1.checked_add(zigzag(commands[i + 1])))
+                        .ok_or(MvtError::InvalidGeometry)?;
+                    i += 2;
+
+                    let p = Point::new(cursor.0 as f64 / extent, cursor.1 as f64 / extent);
+                    if command == MOVE_TO || parts.is_empty() {
+                        parts.push(vec![p]);
+                    } else {
+                        parts.last_mut().unwrap().push(p);
+                    }
+                }
+            }
+            CLOSE_PATH => {
+                if let Some(part) = parts.last_mut()
+                    && let Some(first) = part.first().copied()
+                {
+                    part.push(first);
+                }
+            }
+            _ => return Err(MvtError::InvalidGeometry),
+        }
+    }
+
+    Ok(parts)
+}
+
+/// Splits polygon rings into polygons. Exterior rings have a positive area in tile coordinates
+/// and start a new polygon, the negative rings following them are its holes.
+fn group_rings(rings: Vec<Vec<Point<f64>>>) -> Vec<Vec<Vec<Point<f64>>>> {
+    let mut polygons: Vec<Vec<Vec<Point<f64>>>> = Vec::new();
+
+    for ring in rings {
+        let area = signed_area(&ring);
+        if area == 0.0 {
+            continue;
+        }
+
+        if area > 0.0 || polygons.is_empty() {
+            polygons.push(vec![ring]);
+        } else {
+            polygons.last_mut().unwrap().push(ring);
+        }
+    }
+
+    polygons
+}
+
+fn signed_area(ring: &[Point<f64>]) -> f64 {
+    ring.windows(2)
+        .map(|w| w[0].x() * w[1].y() - w[1].x() * w[0].y())
+        .sum::<f64>()
+        / 2.0
+}
+
+fn zigzag(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+/// Minimal protobuf wire format reader, just enough for the vector tile schema.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn key(&mut self) -> Result<Option<(u64, u8)>, MvtError> {
+        if self.pos >= self.data.len() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        Ok(Some((key >> 3, (key & 0x7) as u8)))
+    }
+
+    fn varint(&mut self) -> Result<u64, MvtError> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = *self.data.get(self.pos).ok_or(MvtError::UnexpectedEof)?;
+            self.pos += 1;
+
+            if shift < 64 {
+                value |= ((b & 0x7f) as u64) << shift;
+            }
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], MvtError> {
+        let end = self.pos.checked_add(n).ok_or(MvtError::UnexpectedEof)?;
+        let slice = self
+            .data
+            .get(self.pos..end)
+            .ok_or(MvtError::UnexpectedEof)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn fixed32(&mut self) -> Result<u32, MvtError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn fixed64(&mut self) -> Result<u64, MvtError> {
+        let b = self.take(8)?;
+        let mut buf = [0; 8];
+        buf.copy_from_slice(b);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], MvtError> {
+        let len = self.varint()? as usize;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<String, MvtError> {
+        let b = self.bytes()?;
+        String::from_utf8(b.to_vec()).map_err(|_| MvtError::InvalidUtf8)
+    }
+
+    fn packed_varints(&mut self) -> Result<Vec<u64>, MvtError> {
+        let mut r = Reader::new(self.bytes()?);
+        let mut values = Vec::new();
+        while r.pos < r.data.len() {
+            values.push(r.varint()?);
+        }
+        Ok(values)
+    }
+
+    fn skip(&mut self, wire_type: u8) -> Result<(), MvtError> {
+        match wire_type {
+            0 => {
+                self.varint()?;
+            }
+            1 => {
+                self.take(8)?;
+            }
+            2 => {
+                self.bytes()?;
+            }
+            5 => {
+                self.take(4)?;
+            }
+            t => return Err(MvtError::UnsupportedWireType(t)),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(buf: &mut Vec<u8>, mut v: u64) {
+        while v >= 0x80 {
+            buf.push((v as u8 & 0x7f) | 0x80);
+            v >>= 7;
+        }
+        buf.push(v as u8);
+    }
+
+    fn varint_field(buf: &mut Vec<u8>, field: u64, v: u64) {
+        varint(buf, field << 3);
+        varint(buf, v);
+    }
+
+    fn bytes_field(buf: &mut Vec<u8>, field: u64, b: &[u8]) {
+        varint(buf, (field << 3) | 2);
+        varint(buf, b.len() as u64);
+        buf.extend_from_slice(b);
+    }
+
+    fn packed_field(buf: &mut Vec<u8>, field: u64, values: &[u64]) {
+        let mut packed = Vec::new();
+        for v in values {
+            varint(&mut packed, *v);
+        }
+        bytes_field(buf, field, &packed);
+    }
+
+    /// A tile with a layer "roads" holding one line string feature with a `name` property.
+    fn road_tile() -> Vec<u8> {
+        let mut value = Vec::new();
+        bytes_field(&mut value, 1, b"Main Street");
+
+        let mut feature = Vec::new();
+        varint_field(&mut feature, 1, 7);
+        packed_field(&mut feature, 2, &[0, 0]);
+        varint_field(&mut feature, 3, GEOM_LINESTRING);
+        packed_field(&mut feature, 4, &[9, 4, 4, 18, 0, 16, 16, 0]);
+
+        let mut layer = Vec::new();
+        bytes_field(&mut layer, 1, b"roads");
+        bytes_field(&mut layer, 2, &feature);
+        bytes_field(&mut layer, 3, b"name");
+        bytes_field(&mut layer, 4, &value);
+        varint_field(&mut layer, 5, 4096);
+
+        let mut tile = Vec::new();
+        bytes_field(&mut tile, 3, &layer);
+        tile
+    }
+
+    fn p(x: f64, y: f64) -> Point<f64> {
+        Point::new(x / 4096.0, y / 4096.0)
+    }
+
+    #[test]
+    fn zigzag_decoding() {
+        assert_eq!(zigzag(0), 0);
+        assert_eq!(zigzag(1), -1);
+        assert_eq!(zigzag(2), 1);
+        assert_eq!(zigzag(3), -2);
+        assert_eq!(zigzag(u64::MAX - 1), i64::MAX);
+        assert_eq!(zigzag(u64::MAX), i64::MIN);
+    }
+
+    #[test]
+    fn geometry_commands() {
+        assert_eq!(
+            decode_geometry(&[9, 50, 34], 4096),
+            Ok(vec![vec![p(25.0, 17.0)]])
+        );
+
+        // a MoveTo with two points starts two parts
+        assert_eq!(
+            decode_geometry(&[17, 10, 14, 3, 9], 4096),
+            Ok(vec![vec![p(5.0, 7.0)], vec![p(3.0, 2.0)]])
+        );
+
+        assert_eq!(
+            decode_geometry(&[9, 4, 4, 18, 0, 16, 16, 0], 4096),
+            Ok(vec![vec![p(2.0, 2.0), p(2.0, 10.0), p(10.0, 10.0)]])
+        );
+
+        // ClosePath repeats the first point of the ring
+        assert_eq!(
+            decode_geometry(&[9, 6, 12, 18, 10, 12, 24, 44, 15], 4096),
+            Ok(vec![vec![
+                p(3.0, 6.0),
+                p(8.0, 12.0),
+                p(20.0, 34.0),
+                p(3.0, 6.0)
+            ]])
+        );
+    }
+
+    #[test]
+    fn malformed_geometry_commands() {
+        // unknown command id
+        assert_eq!(
+            decode_geometry(&[11, 2, 2], 4096),
+            Err(MvtError::InvalidGeometry)
+        );
+        // fewer parameters than the count announces
+        assert_eq!(
+            decode_geometry(&[17, 2, 2], 4096),
+            Err(MvtError::InvalidGeometry)
+        );
+        assert_eq!(
+            decode_geometry(&[(u64::MAX >> 3) << 3 | 1], 4096),
+            Err(MvtError::InvalidGeometry)
+        );
+        // deltas that move the cursor beyond the range of i64
+        assert_eq!(
+            decode_geometry(&[17, u64::MAX - 1, 0, 2, 0], 4096),
+            Err(MvtError::InvalidGeometry)
+        );
+        assert_eq!(
+            decode_geometry(&[9, 0, u64::MAX, 10, 0, 1], 4096),
+            Err(MvtError::InvalidGeometry)
+        );
+    }
+
+    #[test]
+    fn ring_grouping_by_winding() {
+        let square = |x: f64, y: f64, size: f64| {
+            vec![
+                Point::new(x, y),
+                Point::new(x + size, y),
+                Point::new(x + size, y + size),
+                Point::new(x, y + size),
+                Point::new(x, y),
+            ]
+        };
+        let reversed = |mut ring: Vec<Point<f64>>| {
+            ring.reverse();
+            ring
+        };
+
+        let outer = square(0.0, 0.0, 10.0);
+        let hole = reversed(square(2.0, 2.0, 2.0));
+        let second = square(20.0, 0.0, 5.0);
+        let degenerate = vec![
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 2.0),
+            Point::new(1.0, 1.0),
+        ];
+
+        assert!(signed_area(&outer) > 0.0);
+        assert!(signed_area(&hole) < 0.0);
+
+        let polygons = group_rings(vec![
+            outer.clone(),
+            hole.clone(),
+            degenerate,
+            second.clone(),
+        ]);
+        assert_eq!(polygons, vec![vec![outer, hole.clone()], vec![second]]);
+
+        // a leading hole has no exterior to belong to and becomes a polygon of its own
+        assert_eq!(group_rings(vec![hole.clone()]), vec![vec![hole]]);
+    }
+
+    #[test]
+    fn tile_decoding() {
+        let tile = VectorTile::decode(&road_tile()).unwrap();
+
+        let roads = tile.layer("roads").unwrap();
+        assert_eq!(roads.extent, 4096);
+        assert_eq!(roads.features.len(), 1);
+
+        let feature = &roads.features[0];
+        assert_eq!(feature.id, Some(7));
+        assert_eq!(
+            feature.properties.get("name"),
+            Some(&MvtValue::String("Main Street".to_string()))
+        );
+        assert_eq!(
+            feature.geometry,
+            MvtGeometry::LineStrings(vec![vec![p(2.0, 2.0), p(2.0, 10.0), p(10.0, 10.0)]])
+        );
+    }
+
+    #[test]
+    fn empty_tile() {
+        assert_eq!(VectorTile::decode(&[]), Ok(VectorTile::default()));
+    }
+
+    #[test]
+    fn truncated_tile() {
+        let data = road_tile();
+        for len in 1..data.len() {
+            assert_eq!(
+                VectorTile::decode(&data[..len]),
+                Err(MvtError::UnexpectedEof),
+                "truncated to {len} bytes"
+            );
+        }
+
+        // a varint whose last byte is missing
+        assert_eq!(VectorTile::decode(&[0x80]), Err(MvtError::UnexpectedEof));
+    }
+
+    #[test]
+    fn malformed_tile() {
+        // an unknown field with the deprecated group wire type can't be skipped
+        assert_eq!(
+            VectorTile::decode(&[(1 << 3) | 3]),
+            Err(MvtError::UnsupportedWireType(3))
+        );
+
+        let mut layer = Vec::new();
+        bytes_field(&mut layer, 1, &[0xff, 0xfe]);
+        let mut tile = Vec::new();
+        bytes_field(&mut tile, 3, &layer);
+        assert_eq!(VectorTile::decode(&tile), Err(MvtError::InvalidUtf8));
+    }
+
+    #[test]
+    fn invalid_extent() {
+        for extent in [0, 1 << 32] {
+            let mut layer = Vec::new();
+            bytes_field(&mut layer, 1, b"roads");
+            varint_field(&mut layer, 5, extent);
+            let mut tile = Vec::new();
+            bytes_field(&mut tile, 3, &layer);
+            assert_eq!(
+                VectorTile::decode(&tile),
+                Err(MvtError::InvalidExtent),
+                "extent {extent}"
+            );
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use egui::{Color32, Mesh, Pos2, TextureId};
+use geo::Point;
+
+/// Number of quads along each side of an image mesh. The image is georeferenced in
+/// longitude/latitude, so it has to be bent to follow the mercator projection.
+const GRID: usize = 16;
+
+/// An image draped over the map by the positions of its corners, e.g. a scanned floor plan, a
+/// radar frame or an orthophoto. Drawn with [`crate::EMap::image_overlay`] above the tile layers
+/// and below the shapes.
+///
+/// ```no_run
+/// use emap::{EMap, ImageOverlay};
+///
+/// fn radar_ui(ui: &mut egui::Ui, frame: &egui::TextureHandle) {
+///     let bounds = geo::Rect::new(geo::coord! { x: 5.9, y: 45.8 }, geo::coord! { x: 10.5, y: 47.8 });
+///     EMap::new("map")
+///         .image_overlay(ImageOverlay::new(frame, bounds).opacity(0.6))
+///         .show(ui);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageOverlay {
+    pub(crate) texture: TextureId,
+    /// Longitude/latitude of the top left, top right, bottom right and bottom left corner.
+    pub(crate) corners: [Point<f64>; 4],
+    pub(crate) tint: Color32,
+    pub(crate) opacity: f32,
+}
+
+impl ImageOverlay {
+    /// The image stretched over the longitude/latitude `bounds`, north up.
+    pub fn new(texture: impl Into<TextureId>, bounds: geo::Rect<f64>) -> Self {
+        let (min, max) = (bounds.min(), bounds.max());
+        Self::from_corners(
+            texture,
+            [
+                Point::new(min.x, max.y),
+                Point::new(max.x, max.y),
+                Point::new(max.x, min.y),
+                Point::new(min.x, min.y),
+            ],
+        )
+    }
+
+    /// The image placed by the longitude/latitude of its top left, top right, bottom right and
+    /// bottom left corner, so it can be rotated or skewed.
+    pub fn from_corners(texture: impl Into<TextureId>, corners: [Point<f64>; 4]) -> Self {
+        Self {
+            texture: texture.into(),
+            corners,
+            tint: Color32::WHITE,
+            opacity: 1.0,
+        }
+    }
+
+    /// Fades the image, on top of any [`ImageOverlay::tint`], so the two can be set in either
+    /// order.
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Multiplies the image colors, including their alpha, with `tint`. The result is faded by
+    /// [`ImageOverlay::opacity`].
+    pub fn tint(mut self, tint: Color32) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    /// Builds a mesh of the image with `project` placing longitude/latitude on the screen.
+    pub(crate) fn mesh(&self, project: impl Fn(Point<f64>) -> Pos2) -> Mesh {
+        let [tl, tr, br, bl] = self.corners;
+
+        let color = self.tint.gamma_multiply(self.opacity);
+
+        let mut mesh = Mesh::with_texture(self.texture);
+        for row in 0..=GRID {
+            for col in 0..=GRID {
+                let u = col as f64 / GRID as f64;
+                let v = row as f64 / GRID as f64;
+
+                let top = tl + (tr - tl) * u;
+                let bottom = bl + (br - bl) * u;
+                let p = top + (bottom - top) * v;
+
+                mesh.vertices.push(egui::epaint::Vertex {
+                    pos: project(p),
+                    uv: Pos2::new(u as f32, v as f32),
+                    color,
+                });
+            }
+        }
+
+        let width = GRID as u32 + 1;
+        for row in 0..GRID as u32 {
+            for col in 0..GRID as u32 {
+                let i = row * width + col;
+                mesh.add_triangle(i, i + 1, i + width);
+                mesh.add_triangle(i + 1, i + width + 1, i + width);
+            }
+        }
+
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tint_and_opacity() {
+        let bounds = geo::Rect::new(
+            geo::coord! { x: 0.0, y: 0.0 },
+            geo::coord! { x: 1.0, y: 1.0 },
+        );
+        let overlay = ImageOverlay::new(TextureId::default(), bounds);
+        let color = |overlay: ImageOverlay| overlay.mesh(|_| Pos2::ZERO).vertices[0].color;
+
+        let red = Color32::from_rgb(200, 0, 0);
+        let faded_red = red.gamma_multiply(0.5);
+        assert_eq!(color(overlay.tint(red).opacity(0.5)), faded_red);
+        assert_eq!(color(overlay.opacity(0.5).tint(red)), faded_red);
+        assert_eq!(color(overlay.opacity(2.0)), Color32::WHITE);
+        assert_eq!(color(overlay), Color32::WHITE);
+    }
+}
@@ -0,0 +1,64 @@
+use egui::{Align, Align2, Frame, Layout, Rect, Ui, UiBuilder};
+
+/// The gap between boxes stacked in the same corner.
+const SPACING: f32 = 4.0;
+
+/// Places boxes like the attribution and legends into the corners of the map. Boxes sharing an
+/// anchor are stacked, each one further away from the edge than the one shown before it.
+pub(crate) struct CornerPanels {
+    map_rect: Rect,
+    /// How far the next box at an anchor is moved away from the edge.
+    taken: Vec<(Align2, f32)>,
+}
+
+impl CornerPanels {
+    pub fn new(map_rect: Rect) -> Self {
+        Self {
+            map_rect,
+            taken: Vec::new(),
+        }
+    }
+
+    pub fn map_rect(&self) -> Rect {
+        self.map_rect
+    }
+
+    /// Shows a translucent box with `add_contents` at `anchor`.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        anchor: Align2,
+        frame: impl FnOnce(Frame) -> Frame,
+        add_contents: impl FnOnce(&mut Ui),
+    ) {
+        let offset = self
+            .taken
+            .iter()
+            .find(|(a, _)| *a == anchor)
+            .map_or(0.0, |(_, offset)| *offset);
+
+        let mut max_rect = self.map_rect;
+        let layout = if anchor.y() == Align::Max {
+            max_rect.max.y -= offset;
+            Layout::bottom_up(anchor.x())
+        } else {
+            max_rect.min.y += offset;
+            Layout::top_down(anchor.x())
+        };
+
+        let mut child = ui.new_child(UiBuilder::new().max_rect(max_rect).layout(layout));
+
+        let fill = child.visuals().window_fill().gamma_multiply(0.8);
+        let height = frame(Frame::new().fill(fill))
+            .show(&mut child, add_contents)
+            .response
+            .rect
+            .height();
+
+        let offset = offset + height + SPACING;
+        match self.taken.iter_mut().find(|(a, _)| *a == anchor) {
+            Some((_, taken)) => *taken = offset,
+            None => self.taken.push((anchor, offset)),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use egui::{Pos2, Rect};
+use geo::Point;
+
+use crate::{geo_from_pos2, normalized_mercator, reverse_normalized_mercator, scale_rect};
+
+/// The length of the equator in EPSG:3857 meters.
+const EARTH_CIRCUMFERENCE: f64 = 2.0 * std::f64::consts::PI * 6_378_137.0;
+
+/// Converts between the coordinate systems of a map as it was drawn in one frame, see
+/// [`crate::EMapResponse::projector`]:
+///
+/// - geographic longitude/latitude in degrees, as `x`/`y` of a [`Point`],
+/// - normalized web mercator, from `(0, 0)` in the north west to `(1, 1)` in the south east,
+/// - web mercator meters (EPSG:3857), with north up,
+/// - and screen positions.
+///
+/// ```no_run
+/// use egui::{Color32, Stroke};
+/// use emap::EMap;
+///
+/// fn map_ui(ui: &mut egui::Ui) {
+///     let response = EMap::new("map").show(ui);
+///     let projector = response.projector();
+///
+///     let zurich = projector.project(geo::point!(x: 8.54, y: 47.37));
+///     ui.painter_at(projector.rect())
+///         .circle_stroke(zurich, 10.0, Stroke::new(2.0, Color32::RED));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Projector {
+    rect: Rect,
+    /// The screen area the normalized area is mapped to.
+    view: geo::Rect<f64>,
+    /// The normalized mercator area shown.
+    normalized: geo::Rect<f64>,
+    zoom: f64,
+}
+
+impl Projector {
+    pub(crate) fn new(
+        rect: Rect,
+        view: geo::Rect<f64>,
+        normalized: geo::Rect<f64>,
+        zoom: f64,
+    ) -> Self {
+        Self {
+            rect,
+            view,
+            normalized,
+            zoom,
+        }
+    }
+
+    /// The screen area of the map widget.
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    /// The screen position of a longitude/latitude.
+    pub fn project(&self, p: Point<f64>) -> Pos2 {
+        self.normalized_to_screen(normalized_mercator(p))
+    }
+
+    /// The longitude/latitude at a screen position.
+    pub fn unproject(&self, pos: Pos2) -> Point<f64> {
+        reverse_normalized_mercator(self.screen_to_normalized(pos))
+    }
+
+    pub fn normalized_to_screen(&self, p: Point<f64>) -> Pos2 {
+        let p = scale_rect(p, self.normalized, self.view);
+        Pos2::new(p.x() as f32, p.y() as f32)
+    }
+
+    pub fn screen_to_normalized(&self, pos: Pos2) -> Point<f64> {
+        scale_rect(geo_from_pos2(pos), self.view, self.normalized)
+    }
+
+    pub fn mercator_to_screen(&self, p: Point<f64>) -> Pos2 {
+        self.normalized_to_screen(Self::mercator_to_normalized(p))
+    }
+
+    pub fn screen_to_mercator(&self, pos: Pos2) -> Point<f64> {
+        Self::normalized_to_mercator(self.screen_to_normalized(pos))
+    }
+
+    pub fn geo_to_normalized(p: Point<f64>) -> Point<f64> {
+        normalized_mercator(p)
+    }
+
+    pub fn normalized_to_geo(p: Point<f64>) -> Point<f64> {
+        reverse_normalized_mercator(p)
+    }
+
+    pub fn geo_to_mercator(p: Point<f64>) -> Point<f64> {
+        Self::normalized_to_mercator(normalized_mercator(p))
+    }
+
+    pub fn mercator_to_geo(p: Point<f64>) -> Point<f64> {
+        reverse_normalized_mercator(Self::mercator_to_normalized(p))
+    }
+
+    pub fn normalized_to_mercator(p: Point<f64>) -> Point<f64> {
+        Point::new(
+            (p.x() - 0.5) * EARTH_CIRCUMFERENCE,
+            (0.5 - p.y()) * EARTH_CIRCUMFERENCE,
+        )
+    }
+
+    pub fn mercator_to_normalized(p: Point<f64>) -> Point<f64> {
+        Point::new(
+            0.5 + p.x() / EARTH_CIRCUMFERENCE,
+            0.5 - p.y() / EARTH_CIRCUMFERENCE,
+        )
+    }
+}
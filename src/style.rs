@@ -0,0 +1,707 @@
+//! Loading of [`VectorStyle`]s from a practical subset of the
+//! [MapLibre / Mapbox GL style specification](https://maplibre.org/maplibre-style-spec/).
+//!
+//! Supported are `background`, `fill`, `line`, `circle` and the text of `symbol` layers, with
+//! constant, legacy `stops` and `interpolate`/`step` zoom functions for their paint properties,
+//! `minzoom`/`maxzoom`, `visibility` and both legacy and expression filters. Data driven paint
+//! properties, icons, patterns and dashes are ignored.
+
+use std::sync::Arc;
+
+use egui::Color32;
+use serde_json::{Map, Value};
+
+use crate::{
+    Interpolate, MvtFeature, MvtGeometry, MvtValue, TextPaint, VectorPaint, VectorRule,
+    VectorStyle, ZoomStroke, ZoomValue, vector::format_template,
+};
+
+#[derive(Debug)]
+pub enum StyleError {
+    Json(serde_json::Error),
+    MissingLayers,
+}
+
+impl std::fmt::Display for StyleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StyleError::Json(e) => write!(f, "invalid style json: {e}"),
+            StyleError::MissingLayers => write!(f, "style has no layers array"),
+        }
+    }
+}
+
+impl std::error::Error for StyleError {}
+
+impl From<serde_json::Error> for StyleError {
+    fn from(e: serde_json::Error) -> Self {
+        StyleError::Json(e)
+    }
+}
+
+impl VectorStyle {
+    /// Builds a style from a MapLibre / Mapbox GL style document. If `source` is given, only
+    /// layers of that source are used, otherwise all of them.
+    pub fn from_mapbox_json(json: &str, source: Option<&str>) -> Result<Self, StyleError> {
+        let document: Value = serde_json::from_str(json)?;
+        let layers = document
+            .get("layers")
+            .and_then(Value::as_array)
+            .ok_or(StyleError::MissingLayers)?;
+
+        let mut style = VectorStyle::new();
+        for layer in layers.iter().filter_map(Value::as_object) {
+            let layout = layer.get("layout").and_then(Value::as_object);
+            let paint = layer.get("paint").and_then(Value::as_object);
+
+            if layout
+                .and_then(|l| l.get("visibility"))
+                .and_then(Value::as_str)
+                == Some("none")
+            {
+                continue;
+            }
+
+            let kind = layer
+                .get("type")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if kind == "background" {
+                let color = color_property(paint, "background-color", Color32::BLACK);
+                let opacity = number_property(paint, "background-opacity", 1.0).at(0.0);
+                style.background = Some(if opacity < 1.0 {
+                    ZoomValue::constant(color.at(0.0).gamma_multiply(opacity))
+                } else {
+                    color
+                });
+                continue;
+            }
+
+            if source.is_some() && layer.get("source").and_then(Value::as_str) != source {
+                continue;
+            }
+
+            let Some(source_layer) = layer.get("source-layer").and_then(Value::as_str) else {
+                continue;
+            };
+
+            let Some((paint, opacity)) = layer_paint(kind, layout, paint) else {
+                continue;
+            };
+
+            let mut rule = VectorRule::new(source_layer, paint).opacity(opacity);
+
+            let min_zoom = layer.get("minzoom").and_then(Value::as_f64);
+            let max_zoom = layer.get("maxzoom").and_then(Value::as_f64);
+            if min_zoom.is_some() || max_zoom.is_some() {
+                rule = rule.zoom_range(min_zoom.unwrap_or(0.0), max_zoom.unwrap_or(f64::INFINITY));
+            }
+
+            if let Some(filter) = layer.get("filter").cloned() {
+                rule = rule.filter(move |f| eval(&filter, f) == Value::Bool(true));
+            }
+
+            style.rules.push(rule);
+        }
+
+        Ok(style)
+    }
+}
+
+type Properties<'a> = Option<&'a Map<String, Value>>;
+
+fn layer_paint(
+    kind: &str,
+    layout: Properties<'_>,
+    paint: Properties<'_>,
+) -> Option<(VectorPaint, ZoomValue<f32>)> {
+    match kind {
+        "fill" => {
+            let outline = paint
+                .and_then(|p| p.get("fill-outline-color"))
+                .and_then(|v| zoom_value(v, parse_color))
+                .map(|color| ZoomStroke::new(1.0, color));
+            Some((
+                VectorPaint::Fill {
+                    color: color_property(paint, "fill-color", Color32::BLACK),
+                    outline,
+                },
+                number_property(paint, "fill-opacity", 1.0),
+            ))
+        }
+        "line" => Some((
+            VectorPaint::Line(ZoomStroke::new(
+                number_property(paint, "line-width", 1.0),
+                color_property(paint, "line-color", Color32::BLACK),
+            )),
+            number_property(paint, "line-opacity", 1.0),
+        )),
+        "circle" => Some((
+            VectorPaint::Point {
+                radius: number_property(paint, "circle-radius", 5.0),
+                color: color_property(paint, "circle-color", Color32::BLACK),
+            },
+            number_property(paint, "circle-opacity", 1.0),
+        )),
+        "symbol" => {
+            let field = layout?.get("text-field")?.clone();
+            let text: crate::FeatureText = match field {
+                Value::String(template) => Arc::new(move |f| format_template(&template, f)),
+                expr => Arc::new(move |f| {
+                    let text = to_string(&eval(&expr, f));
+                    (!text.is_empty()).then_some(text)
+                }),
+            };
+
+            let halo_width = number_property(paint, "text-halo-width", 0.0);
+            let halo = paint
+                .and_then(|p| p.get("text-halo-color"))
+                .and_then(|v| zoom_value(v, parse_color))
+                .map(|color| ZoomStroke::new(halo_width, color));
+
+            Some((
+                VectorPaint::Text(TextPaint {
+                    text,
+                    size: number_property(layout, "text-size", 16.0),
+                    color: color_property(paint, "text-color", Color32::BLACK),
+                    halo,
+                }),
+                number_property(paint, "text-opacity", 1.0),
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn number_property(properties: Properties<'_>, name: &str, default: f32) -> ZoomValue<f32> {
+    properties
+        .and_then(|p| p.get(name))
+        .and_then(|v| zoom_value(v, |v| v.as_f64().map(|n| n as f32)))
+        .unwrap_or(ZoomValue::constant(default))
+}
+
+fn color_property(properties: Properties<'_>, name: &str, default: Color32) -> ZoomValue<Color32> {
+    properties
+        .and_then(|p| p.get(name))
+        .and_then(|v| zoom_value(v, parse_color))
+        .unwrap_or(ZoomValue::constant(default))
+}
+
+/// Parses a constant, a legacy `{"base": .., "stops": [..]}` function or an `interpolate`/`step`
+/// expression over `["zoom"]`.
+fn zoom_value<T: Interpolate>(
+    value: &Value,
+    parse: impl Fn(&Value) -> Option<T>,
+) -> Option<ZoomValue<T>> {
+    let parse_stops = |stops: &[Value]| -> Option<Vec<(f64, T)>> {
+        stops
+            .chunks_exact(2)
+            .map(|s| Some((s[0].as_f64()?, parse(&s[1])?)))
+            .collect()
+    };
+
+    match value {
+        Value::Object(function) => {
+            let base = function.get("base").and_then(Value::as_f64).unwrap_or(1.0);
+            let stops = function
+                .get("stops")?
+                .as_array()?
+                .iter()
+                .map(|s| Some((s.get(0)?.as_f64()?, parse(s.get(1)?)?)))
+                .collect::<Option<Vec<_>>>()?;
+
+            if stops.is_empty() {
+                return None;
+            }
+            if function.get("type").and_then(Value::as_str) == Some("interval") {
+                let default = stops[0].1.clone();
+                return Some(ZoomValue::step(default, stops));
+            }
+            Some(ZoomValue::interpolate(base, stops))
+        }
+        Value::Array(expr) => match expr.first().and_then(Value::as_str) {
+            Some("interpolate" | "interpolate-hcl" | "interpolate-lab") => {
+                if expr.get(2)? != &Value::Array(vec!["zoom".into()]) {
+                    return None;
+                }
+
+                let interpolation = expr.get(1)?.as_array()?;
+                let base = match interpolation.first().and_then(Value::as_str) {
+                    Some("exponential") => interpolation.get(1)?.as_f64()?,
+                    _ => 1.0,
+                };
+
+                let stops = parse_stops(expr.get(3..)?)?;
+                (!stops.is_empty()).then(|| ZoomValue::interpolate(base, stops))
+            }
+            Some("step") => {
+                if expr.get(1)? != &Value::Array(vec!["zoom".into()]) {
+                    return None;
+                }
+
+                let default = parse(expr.get(2)?)?;
+                Some(ZoomValue::step(default, parse_stops(expr.get(3..)?)?))
+            }
+            _ => parse(value).map(ZoomValue::constant),
+        },
+        _ => parse(value).map(ZoomValue::constant),
+    }
+}
+
+/// Parses CSS colors as used in styles: hex, `rgb[a]()`, `hsl[a]()` and a few names.
+fn parse_color(value: &Value) -> Option<Color32> {
+    let s = value.as_str()?.trim().to_ascii_lowercase();
+
+    if let Some(hex) = s.strip_prefix('#') {
+        let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+        let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return match hex.len() {
+            3 | 4 => {
+                let a = if hex.len() == 4 { digit(3)? * 17 } else { 255 };
+                Some(Color32::from_rgba_unmultiplied(
+                    digit(0)? * 17,
+                    digit(1)? * 17,
+                    digit(2)? * 17,
+                    a,
+                ))
+            }
+            6 | 8 => {
+                let a = if hex.len() == 8 { byte(6)? } else { 255 };
+                Some(Color32::from_rgba_unmultiplied(
+                    byte(0)?,
+                    byte(2)?,
+                    byte(4)?,
+                    a,
+                ))
+            }
+            _ => None,
+        };
+    }
+
+    if let Some((name, args)) = s.strip_suffix(')').and_then(|s| s.split_once('(')) {
+        let args = args
+            .split(',')
+            .map(|a| a.trim().trim_end_matches('%').parse::<f32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let alpha = |i: usize| (args.get(i).copied().unwrap_or(1.0).clamp(0.0, 1.0) * 255.0) as u8;
+
+        return match (name.trim(), args.len()) {
+            ("rgb" | "rgba", 3 | 4) => Some(Color32::from_rgba_unmultiplied(
+                args[0] as u8,
+                args[1] as u8,
+                args[2] as u8,
+                alpha(3),
+            )),
+            ("hsl" | "hsla", 3 | 4) => {
+                let [r, g, b] = hsl_to_rgb(args[0], args[1] / 100.0, args[2] / 100.0);
+                Some(Color32::from_rgba_unmultiplied(r, g, b, alpha(3)))
+            }
+            _ => None,
+        };
+    }
+
+    match s.as_str() {
+        "transparent" => Some(Color32::TRANSPARENT),
+        "black" => Some(Color32::BLACK),
+        "white" => Some(Color32::WHITE),
+        "red" => Some(Color32::from_rgb(255, 0, 0)),
+        "green" => Some(Color32::from_rgb(0, 128, 0)),
+        "blue" => Some(Color32::from_rgb(0, 0, 255)),
+        "yellow" => Some(Color32::from_rgb(255, 255, 0)),
+        "orange" => Some(Color32::from_rgb(255, 165, 0)),
+        "gray" | "grey" => Some(Color32::from_rgb(128, 128, 128)),
+        _ => None,
+    }
+}
+
+fn hsl_to_rgb(h: f32, s: f32, l: f32) -> [u8; 3] {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = l - c / 2.0;
+    let to_byte = |v: f32| ((v + m).clamp(0.0, 1.0) * 255.0).round() as u8;
+    [to_byte(r), to_byte(g), to_byte(b)]
+}
+
+fn property(feature: &MvtFeature, key: &str) -> Value {
+    match key {
+        "$type" => geometry_type(feature),
+        "$id" => feature.id.map(Value::from).unwrap_or(Value::Null),
+        key => feature
+            .properties
+            .get(key)
+            .map(mvt_to_json)
+            .unwrap_or(Value::Null),
+    }
+}
+
+fn geometry_type(feature: &MvtFeature) -> Value {
+    match feature.geometry {
+        MvtGeometry::Points(_) => "Point".into(),
+        MvtGeometry::LineStrings(_) => "LineString".into(),
+        MvtGeometry::Polygons(_) => "Polygon".into(),
+    }
+}
+
+fn mvt_to_json(value: &MvtValue) -> Value {
+    match value {
+        MvtValue::String(s) => Value::String(s.clone()),
+        MvtValue::Bool(b) => Value::Bool(*b),
+        MvtValue::Int(v) | MvtValue::SInt(v) => Value::from(*v),
+        MvtValue::UInt(v) => Value::from(*v),
+        v => v.as_f64().map(Value::from).unwrap_or(Value::Null),
+    }
+}
+
+fn to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        v => v.to_string(),
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Option<std::cmp::Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+fn equals(a: &Value, b: &Value) -> bool {
+    compare(a, b) == Some(std::cmp::Ordering::Equal) || (a.is_null() && b.is_null())
+}
+
+/// Evaluates a filter or value expression, in either the legacy or the expression syntax.
+fn eval(expr: &Value, feature: &MvtFeature) -> Value {
+    let Some((op, args)) = expr
+        .as_array()
+        .and_then(|a| a.split_first())
+        .and_then(|(op, args)| Some((op.as_str()?, args)))
+    else {
+        return expr.clone();
+    };
+
+    // legacy filters name the property directly instead of using a `get` expression
+    let legacy = args.first().is_some_and(Value::is_string);
+    let operand = |i: usize| match args.get(i) {
+        Some(Value::String(key)) if legacy && i == 0 => property(feature, key),
+        Some(v) if legacy => v.clone(),
+        Some(v) => eval(v, feature),
+        None => Value::Null,
+    };
+    let all_true = |args: &[Value]| args.iter().all(|a| eval(a, feature) == Value::Bool(true));
+
+    match op {
+        "get" => args
+            .first()
+            .and_then(Value::as_str)
+            .map(|key| property(feature, key))
+            .unwrap_or(Value::Null),
+        "has" => Value::Bool(
+            args.first()
+                .and_then(Value::as_str)
+                .is_some_and(|key| !property(feature, key).is_null()),
+        ),
+        "!has" => Value::Bool(
+            args.first()
+                .and_then(Value::as_str)
+                .is_some_and(|key| property(feature, key).is_null()),
+        ),
+        "literal" => args.first().cloned().unwrap_or(Value::Null),
+        "geometry-type" => geometry_type(feature),
+        "id" => property(feature, "$id"),
+        "==" => Value::Bool(equals(&operand(0), &operand(1))),
+        "!=" => Value::Bool(!equals(&operand(0), &operand(1))),
+        "<" | "<=" | ">" | ">=" => {
+            let ordering = compare(&operand(0), &operand(1));
+            Value::Bool(ordering.is_some_and(|o| match op {
+                "<" => o.is_lt(),
+                "<=" => o.is_le(),
+                ">" => o.is_gt(),
+                _ => o.is_ge(),
+            }))
+        }
+        "in" | "!in" if legacy => {
+            let value = operand(0);
+            let found = args[1..].iter().any(|v| equals(&value, v));
+            Value::Bool(found == (op == "in"))
+        }
+        "in" => {
+            let needle = operand(0);
+            Value::Bool(match operand(1) {
+                Value::Array(values) => values.iter().any(|v| equals(&needle, v)),
+                Value::String(s) => needle.as_str().is_some_and(|n| s.contains(n)),
+                _ => false,
+            })
+        }
+        "all" => Value::Bool(all_true(args)),
+        "any" => Value::Bool(args.iter().any(|a| eval(a, feature) == Value::Bool(true))),
+        "none" => Value::Bool(args.iter().all(|a| eval(a, feature) != Value::Bool(true))),
+        "!" => Value::Bool(operand(0) != Value::Bool(true)),
+        "match" => {
+            let input = operand(0);
+            let mut branches = args.get(1..).unwrap_or_default().chunks_exact(2);
+            for branch in branches.by_ref() {
+                let matches = match &branch[0] {
+                    Value::Array(labels) => labels.iter().any(|l| equals(&input, l)),
+                    label => equals(&input, label),
+                };
+                if matches {
+                    return eval(&branch[1], feature);
+                }
+            }
+            branches
+                .remainder()
+                .first()
+                .map(|fallback| eval(fallback, feature))
+                .unwrap_or(Value::Null)
+        }
+        "case" => {
+            let mut branches = args.chunks_exact(2);
+            for branch in branches.by_ref() {
+                if eval(&branch[0], feature) == Value::Bool(true) {
+                    return eval(&branch[1], feature);
+                }
+            }
+            branches
+                .remainder()
+                .first()
+                .map(|fallback| eval(fallback, feature))
+                .unwrap_or(Value::Null)
+        }
+        "coalesce" => args
+            .iter()
+            .map(|a| eval(a, feature))
+            .find(|v| !v.is_null())
+            .unwrap_or(Value::Null),
+        "concat" => Value::String(args.iter().map(|a| to_string(&eval(a, feature))).collect()),
+        // ["format", text, {options}, text, {options}, ...], the options are ignored
+        "format" => Value::String(
+            args.iter()
+                .filter(|a| !a.is_object())
+                .map(|a| to_string(&eval(a, feature)))
+                .collect(),
+        ),
+        "to-string" => Value::String(to_string(&operand(0))),
+        "upcase" => Value::String(to_string(&operand(0)).to_uppercase()),
+        "downcase" => Value::String(to_string(&operand(0)).to_lowercase()),
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn road() -> MvtFeature {
+        MvtFeature {
+            id: Some(42),
+            properties: HashMap::from([
+                ("class".to_string(), MvtValue::String("primary".to_string())),
+                ("lanes".to_string(), MvtValue::UInt(2)),
+                (
+                    "name".to_string(),
+                    MvtValue::String("Main Street".to_string()),
+                ),
+            ]),
+            geometry: MvtGeometry::LineStrings(Vec::new()),
+        }
+    }
+
+    fn matches(filter: Value) -> bool {
+        eval(&filter, &road()) == Value::Bool(true)
+    }
+
+    #[test]
+    fn legacy_filters() {
+        assert!(matches(json!(["==", "class", "primary"])));
+        assert!(!matches(json!(["==", "class", "secondary"])));
+        assert!(matches(json!(["!=", "class", "secondary"])));
+        assert!(matches(json!([">=", "lanes", 2])));
+        assert!(!matches(json!(["<", "lanes", 2])));
+        assert!(matches(json!(["in", "class", "motorway", "primary"])));
+        assert!(matches(json!(["!in", "class", "motorway", "trunk"])));
+        assert!(matches(json!(["has", "name"])));
+        assert!(matches(json!(["!has", "ref"])));
+        assert!(matches(json!(["==", "$type", "LineString"])));
+        assert!(matches(json!(["==", "$id", 42])));
+        assert!(matches(json!([
+            "all",
+            ["==", "class", "primary"],
+            ["any", ["==", "lanes", 1], ["==", "lanes", 2]],
+            ["none", ["has", "tunnel"]],
+        ])));
+    }
+
+    #[test]
+    fn expression_filters() {
+        assert!(matches(json!(["==", ["get", "class"], "primary"])));
+        assert!(!matches(json!(["==", ["get", "class"], "secondary"])));
+        assert!(matches(json!([">", ["get", "lanes"], 1])));
+        assert!(matches(json!(["==", ["geometry-type"], "LineString"])));
+        assert!(matches(json!(["==", ["id"], 42])));
+        assert!(matches(json!([
+            "in",
+            ["get", "class"],
+            ["literal", ["motorway", "primary"]],
+        ])));
+        assert!(matches(json!(["!", ["has", "tunnel"]])));
+        assert!(matches(json!([
+            "match",
+            ["get", "class"],
+            ["motorway", "trunk"],
+            false,
+            "primary",
+            true,
+            false,
+        ])));
+        assert!(matches(json!([
+            "case",
+            ["==", ["get", "lanes"], 1],
+            false,
+            true,
+        ])));
+        assert_eq!(
+            eval(
+                &json!(["concat", ["upcase", ["get", "name"]], " ", ["get", "lanes"]]),
+                &road()
+            ),
+            json!("MAIN STREET 2")
+        );
+    }
+
+    #[test]
+    fn legacy_and_expression_filters_agree() {
+        let pairs = [
+            (
+                json!(["==", "class", "primary"]),
+                json!(["==", ["get", "class"], "primary"]),
+            ),
+            (
+                json!(["<=", "lanes", 1]),
+                json!(["<=", ["get", "lanes"], 1]),
+            ),
+            (json!(["has", "ref"]), json!(["has", "ref"])),
+        ];
+        for (legacy, expression) in pairs {
+            assert_eq!(matches(legacy.clone()), matches(expression), "{legacy}");
+        }
+    }
+
+    #[test]
+    fn interpolate_and_step() {
+        let number = |v: &Value| v.as_f64().map(|n| n as f32);
+
+        let linear = zoom_value(
+            &json!(["interpolate", ["linear"], ["zoom"], 10, 1, 14, 5]),
+            number,
+        )
+        .unwrap();
+        assert_eq!(linear.at(8.0), 1.0);
+        assert_eq!(linear.at(12.0), 3.0);
+        assert_eq!(linear.at(16.0), 5.0);
+
+        let exponential = zoom_value(
+            &json!(["interpolate", ["exponential", 2], ["zoom"], 0, 0, 2, 3]),
+            number,
+        )
+        .unwrap();
+        assert_eq!(exponential.at(1.0), 1.0);
+
+        let step = zoom_value(&json!(["step", ["zoom"], 1, 10, 2, 14, 4]), number).unwrap();
+        assert_eq!(step.at(9.9), 1.0);
+        assert_eq!(step.at(10.0), 2.0);
+        assert_eq!(step.at(13.0), 2.0);
+        assert_eq!(step.at(15.0), 4.0);
+
+        let legacy = zoom_value(&json!({"base": 1, "stops": [[10, 1], [14, 5]]}), number).unwrap();
+        assert_eq!(legacy.at(12.0), 3.0);
+
+        let interval = zoom_value(
+            &json!({"type": "interval", "stops": [[10, 1], [14, 5]]}),
+            number,
+        )
+        .unwrap();
+        assert_eq!(interval.at(13.0), 1.0);
+
+        assert_eq!(zoom_value(&json!(2.5), number).unwrap().at(3.0), 2.5);
+        // data driven interpolation isn't supported
+        assert!(
+            zoom_value(
+                &json!(["interpolate", ["linear"], ["get", "lanes"], 1, 1]),
+                number
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn colors() {
+        let color = |s: &str| parse_color(&json!(s));
+
+        assert_eq!(color("#f00"), Some(Color32::from_rgb(255, 0, 0)));
+        assert_eq!(
+            color("#00ff0080"),
+            Some(Color32::from_rgba_unmultiplied(0, 255, 0, 128))
+        );
+        assert_eq!(
+            color("rgb(10, 20, 30)"),
+            Some(Color32::from_rgb(10, 20, 30))
+        );
+        assert_eq!(
+            color("rgba(10, 20, 30, 0.5)"),
+            Some(Color32::from_rgba_unmultiplied(10, 20, 30, 127))
+        );
+        assert_eq!(
+            color("hsl(0, 100%, 50%)"),
+            Some(Color32::from_rgb(255, 0, 0))
+        );
+        assert_eq!(
+            color("hsl(120, 100%, 25%)"),
+            Some(Color32::from_rgb(0, 128, 0))
+        );
+        assert_eq!(
+            color("hsla(240, 100%, 50%, 0.5)"),
+            Some(Color32::from_rgba_unmultiplied(0, 0, 255, 127))
+        );
+        assert_eq!(color("HSL(0, 0%, 100%)"), Some(Color32::WHITE));
+        assert_eq!(color("transparent"), Some(Color32::TRANSPARENT));
+        assert_eq!(color("#12345"), None);
+        assert_eq!(color("rgb(1, 2)"), None);
+        assert_eq!(color("chartreuse-ish"), None);
+    }
+
+    #[test]
+    fn text_field_templates() {
+        let feature = road();
+        assert_eq!(
+            format_template("{name} ({lanes})", &feature),
+            Some("Main Street (2)".to_string())
+        );
+        // missing properties are replaced by nothing, like MapLibre does
+        assert_eq!(
+            format_template("{ref} {name}", &feature),
+            Some("Main Street".to_string())
+        );
+        assert_eq!(format_template("{ref}", &feature), None);
+        assert_eq!(
+            format_template("{name", &feature),
+            Some("{name".to_string())
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use egui::{Color32, Mesh, Pos2};
+use geo::{LineString, Point, Polygon, TriangulateEarcut};
+
+/// Triangles covering a polygon, with its holes cut out.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Triangulation {
+    pub vertices: Vec<Point<f64>>,
+    pub indices: Vec<u32>,
+}
+
+impl Triangulation {
+    pub fn from_rings(rings: &[Vec<Point<f64>>]) -> Self {
+        let Some((exterior, interiors)) = rings.split_first() else {
+            return Self::default();
+        };
+
+        let polygon = Polygon::new(
+            LineString::from(exterior.clone()),
+            interiors
+                .iter()
+                .map(|ring| LineString::from(ring.clone()))
+                .collect(),
+        );
+        Self::from_polygon(&polygon)
+    }
+
+    pub fn from_polygon(polygon: &Polygon<f64>) -> Self {
+        // earcut expects closed rings with at least three distinct points
+        if polygon.exterior().0.len() < 4 {
+            return Self::default();
+        }
+
+        let polygon = if polygon.interiors().iter().any(|r| r.0.len() < 4) {
+            Polygon::new(
+                polygon.exterior().clone(),
+                polygon
+                    .interiors()
+                    .iter()
+                    .filter(|r| r.0.len() >= 4)
+                    .cloned()
+                    .collect(),
+            )
+        } else {
+            polygon.clone()
+        };
+
+        let raw = polygon.earcut_triangles_raw();
+
+        Self {
+            vertices: raw
+                .vertices
+                .chunks_exact(2)
+                .map(|c| Point::new(c[0], c[1]))
+                .collect(),
+            indices: raw.triangle_indices.iter().map(|i| *i as u32).collect(),
+        }
+    }
+
+    /// Appends the triangles to `mesh`, mapping every vertex to the screen with `project`.
+    pub fn add_to_mesh(
+        &self,
+        mesh: &mut Mesh,
+        color: Color32,
+        project: impl Fn(Point<f64>) -> Pos2,
+    ) {
+        let offset = mesh.vertices.len() as u32;
+        for v in &self.vertices {
+            mesh.colored_vertex(project(*v), color);
+        }
+        mesh.indices.extend(self.indices.iter().map(|i| i + offset));
+    }
+}
@@ -12,6 +12,12 @@ pub static DEFAULT_TILE_LOADER: LazyLock<DummyLoader> = LazyLock::new(|| DummyLo
 
 pub trait TileLoader {
     fn tile(&self, url: String, tile_id: &TileId, ctx: Context) -> Option<Arc<ColorImage>>;
+
+    /// The undecoded response body for `url`, used for vector tiles. Loaders that only deal with
+    /// raster tiles can keep the default implementation.
+    fn tile_data(&self, _url: String, _tile_id: &TileId, _ctx: Context) -> Option<Arc<[u8]>> {
+        None
+    }
 }
 
 /// This loader just loads the egui::ColorImage example image, which isn't very useful.
@@ -36,15 +42,65 @@ mod tokio_loader {
 
     use super::*;
 
+    #[derive(Clone, Copy)]
+    enum Kind {
+        Image,
+        Data,
+    }
+
     enum Fetch {
         Pending,
         Done(Arc<ColorImage>),
+        Data(Arc<[u8]>),
+        /// An error response or an image that can't be decoded, which isn't requested again.
+        Failed,
+    }
+
+    type Request = (TileId, String, Context, Kind);
+
+    /// Decodes `b` according to `kind` and makes it available to the next `tile`/`tile_data` call.
+    fn store(
+        tiles: Arc<Mutex<HashMap<String, Fetch>>>,
+        key: String,
+        kind: Kind,
+        b: impl AsRef<[u8]> + Send + 'static,
+        ctx: Context,
+    ) {
+        match kind {
+            Kind::Image => {
+                tokio::task::spawn_blocking(move || {
+                    let Ok(image) = image::load_from_memory(b.as_ref()) else {
+                        tiles.lock().unwrap().insert(key, Fetch::Failed);
+                        return;
+                    };
+                    let size = [image.width() as _, image.height() as _];
+                    let image_buffer = image.to_rgba8();
+                    let pixels = image_buffer.as_flat_samples();
+
+                    let color_image =
+                        egui::ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
+
+                    tiles
+                        .lock()
+                        .unwrap()
+                        .insert(key, Fetch::Done(color_image.into()));
+                    ctx.request_repaint();
+                });
+            }
+            Kind::Data => {
+                tiles
+                    .lock()
+                    .unwrap()
+                    .insert(key, Fetch::Data(b.as_ref().into()));
+                ctx.request_repaint();
+            }
+        }
     }
 
     #[cfg(feature = "tokio")]
     pub struct TokioTileLoader {
-        tx: Sender<(TileId, String, Context)>,
-        tiles: Arc<Mutex<HashMap<TileId, Fetch>>>,
+        tx: Sender<Request>,
+        tiles: Arc<Mutex<HashMap<String, Fetch>>>,
     }
 
     #[cfg(feature = "tokio")]
@@ -60,16 +116,16 @@ mod tokio_loader {
                 rt.block_on(async move {
                     let client = Arc::new(ClientBuilder::default().build().unwrap());
                     loop {
-                        let (tile_id, url, ctx): (TileId, String, Context) =
-                            rx.recv().await.unwrap();
+                        let (_tile_id, url, ctx, kind): Request = rx.recv().await.unwrap();
                         let ts = tiles.clone();
                         {
-                            ts.lock().unwrap().insert(tile_id, Fetch::Pending);
+                            ts.lock().unwrap().insert(url.clone(), Fetch::Pending);
                         }
                         let ts = tiles.clone();
                         let c = client.clone();
                         tokio::spawn(async move {
                             let client = c;
+                            let key = url.clone();
 
                             let user_agent =
                                 format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
@@ -80,24 +136,14 @@ mod tokio_loader {
                                 .send()
                                 .await
                                 .unwrap();
+                            let status = r.status();
                             let b = r.bytes().await.unwrap();
 
-                            tokio::task::spawn_blocking(move || {
-                                let image = image::load_from_memory(&b.clone()).unwrap();
-                                let size = [image.width() as _, image.height() as _];
-                                let image_buffer = image.to_rgba8();
-                                let pixels = image_buffer.as_flat_samples();
-
-                                let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                                    size,
-                                    pixels.as_slice(),
-                                );
-
-                                ts.lock()
-                                    .unwrap()
-                                    .insert(tile_id, Fetch::Done(color_image.into()));
-                                ctx.request_repaint();
-                            });
+                            if status.is_success() {
+                                store(ts, key, kind, b, ctx);
+                            } else {
+                                ts.lock().unwrap().insert(key, Fetch::Failed);
+                            }
                         });
                     }
                 });
@@ -118,27 +164,45 @@ mod tokio_loader {
     impl TileLoader for TokioTileLoader {
         fn tile(&self, url: String, tile_id: &TileId, ctx: Context) -> Option<Arc<ColorImage>> {
             let t = self.tiles.lock().unwrap();
-            match t.get(tile_id) {
-                Some(Fetch::Pending) => None,
+            match t.get(&url) {
                 Some(Fetch::Done(c)) => Some(c.clone()),
+                Some(_) => None,
+                None => {
+                    self.tx
+                        .blocking_send((*tile_id, url, ctx, Kind::Image))
+                        .unwrap();
+                    None
+                }
+            }
+        }
+
+        fn tile_data(&self, url: String, tile_id: &TileId, ctx: Context) -> Option<Arc<[u8]>> {
+            let t = self.tiles.lock().unwrap();
+            match t.get(&url) {
+                Some(Fetch::Data(d)) => Some(d.clone()),
+                Some(_) => None,
                 None => {
-                    self.tx.blocking_send((*tile_id, url, ctx)).unwrap();
+                    self.tx
+                        .blocking_send((*tile_id, url, ctx, Kind::Data))
+                        .unwrap();
                     None
                 }
             }
         }
     }
 
+    /// Fetches tiles like [`TokioTileLoader`] and keeps a copy of them below `dir`, laid out like
+    /// their urls as `{host}/{path}`, so one cache directory can be shared by all providers.
     #[cfg(feature = "caching")]
     pub struct CachingTileLoader {
-        tx: Sender<(TileId, String, Context)>,
-        tiles: Arc<Mutex<HashMap<TileId, Fetch>>>,
+        tx: Sender<Request>,
+        tiles: Arc<Mutex<HashMap<String, Fetch>>>,
     }
 
     #[cfg(feature = "caching")]
     impl CachingTileLoader {
         pub fn new(dir: impl Into<std::path::PathBuf>) -> Self {
-            let (tx, mut rx) = tokio::sync::mpsc::channel::<(TileId, String, Context)>(1024);
+            let (tx, mut rx) = tokio::sync::mpsc::channel::<Request>(1024);
             let tiles = Arc::new(Mutex::new(HashMap::new()));
             let t1 = tiles.clone();
 
@@ -152,12 +216,12 @@ mod tokio_loader {
                 rt.block_on(async move {
                     let client = Arc::new(ClientBuilder::default().build().unwrap());
                     loop {
-                        let Some((tile_id, url, ctx)) = rx.recv().await else {
+                        let Some((_tile_id, url, ctx, kind)) = rx.recv().await else {
                             break;
                         };
                         let ts = tiles.clone();
                         {
-                            ts.lock().unwrap().insert(tile_id, Fetch::Pending);
+                            ts.lock().unwrap().insert(url.clone(), Fetch::Pending);
                         }
                         let ts = tiles.clone();
                         let c = client.clone();
@@ -165,35 +229,13 @@ mod tokio_loader {
                         tokio::spawn(async move {
                             let client = c;
                             let cache_dir = cd;
+                            let key = url.clone();
 
-                            let path = cache_dir
-                                .join(format!("{}/{}/{}", tile_id.z, tile_id.x, tile_id.y));
+                            let path = cache_path(&cache_dir, &url);
                             let dir = path.parent().unwrap();
 
-                            let exists = tokio::fs::metadata(&path).await;
-                            let exists = exists.is_ok();
-                            if exists {
-                                let b =
-                                    bytes::Bytes::from_owner(tokio::fs::read(&path).await.unwrap());
-
-                                let ctx = ctx.clone();
-                                let ts = ts.clone();
-                                tokio::task::spawn_blocking(move || {
-                                    let image = image::load_from_memory(&b.clone()).unwrap();
-                                    let size = [image.width() as _, image.height() as _];
-                                    let image_buffer = image.to_rgba8();
-                                    let pixels = image_buffer.as_flat_samples();
-
-                                    let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                                        size,
-                                        pixels.as_slice(),
-                                    );
-
-                                    ts.lock()
-                                        .unwrap()
-                                        .insert(tile_id, Fetch::Done(color_image.into()));
-                                    ctx.request_repaint();
-                                });
+                            if let Ok(b) = tokio::fs::read(&path).await {
+                                store(ts, key, kind, bytes::Bytes::from_owner(b), ctx);
                                 return;
                             }
 
@@ -213,22 +255,9 @@ mod tokio_loader {
                                 tokio::fs::create_dir_all(&dir).await.unwrap();
                                 tokio::fs::write(path, b.clone()).await.unwrap();
 
-                                tokio::task::spawn_blocking(move || {
-                                    let image = image::load_from_memory(&b.clone()).unwrap();
-                                    let size = [image.width() as _, image.height() as _];
-                                    let image_buffer = image.to_rgba8();
-                                    let pixels = image_buffer.as_flat_samples();
-
-                                    let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                                        size,
-                                        pixels.as_slice(),
-                                    );
-
-                                    ts.lock()
-                                        .unwrap()
-                                        .insert(tile_id, Fetch::Done(color_image.into()));
-                                    ctx.request_repaint();
-                                });
+                                store(ts, key, kind, b, ctx);
+                            } else {
+                                ts.lock().unwrap().insert(key, Fetch::Failed);
                             }
                         });
                     }
@@ -239,24 +268,109 @@ mod tokio_loader {
         }
     }
 
+    /// The file below `dir` a tile fetched from `url` is cached in. The query, which may contain
+    /// an API key, only goes into the file name as a hash.
+    #[cfg(feature = "caching")]
+    fn cache_path(dir: &std::path::Path, url: &str) -> std::path::PathBuf {
+        let Ok(url) = reqwest::Url::parse(url) else {
+            return dir.join(format!("{:016x}", fnv1a(url)));
+        };
+
+        let host = url.host_str().unwrap_or("local");
+        let mut path = match url.port() {
+            Some(port) => dir.join(format!("{host}_{port}")),
+            None => dir.join(host),
+        };
+        for segment in url.path_segments().into_iter().flatten() {
+            if !segment.is_empty() && segment != "." && segment != ".." {
+                path.push(segment);
+            }
+        }
+
+        if let Some(query) = url.query() {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            path.set_file_name(format!("{name}_{:016x}", fnv1a(query)));
+        }
+
+        path
+    }
+
+    /// The 64 bit FNV-1a hash of `s`, which unlike the std hashers is the same across Rust
+    /// releases, so cached tiles are found again after an update.
+    #[cfg(feature = "caching")]
+    fn fnv1a(s: &str) -> u64 {
+        s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
     #[cfg(feature = "caching")]
     impl TileLoader for CachingTileLoader {
         fn tile(&self, url: String, tile_id: &TileId, ctx: Context) -> Option<Arc<ColorImage>> {
             let mut t = self.tiles.lock().unwrap();
-            match t.get(tile_id) {
-                Some(Fetch::Pending) => None,
-                Some(Fetch::Done(_)) => t.remove(tile_id).and_then(|f| {
+            match t.get(&url) {
+                Some(Fetch::Done(_)) => t.remove(&url).and_then(|f| {
                     if let Fetch::Done(c) = f {
                         Some(c)
                     } else {
                         None
                     }
                 }),
+                Some(_) => None,
                 None => {
-                    self.tx.blocking_send((*tile_id, url, ctx)).unwrap();
+                    self.tx
+                        .blocking_send((*tile_id, url, ctx, Kind::Image))
+                        .unwrap();
                     None
                 }
             }
         }
+
+        fn tile_data(&self, url: String, tile_id: &TileId, ctx: Context) -> Option<Arc<[u8]>> {
+            let mut t = self.tiles.lock().unwrap();
+            match t.get(&url) {
+                Some(Fetch::Data(_)) => t.remove(&url).and_then(|f| {
+                    if let Fetch::Data(d) = f {
+                        Some(d)
+                    } else {
+                        None
+                    }
+                }),
+                Some(_) => None,
+                None => {
+                    self.tx
+                        .blocking_send((*tile_id, url, ctx, Kind::Data))
+                        .unwrap();
+                    None
+                }
+            }
+        }
+    }
+
+    #[cfg(all(test, feature = "caching"))]
+    mod tests {
+        use std::path::Path;
+
+        use super::*;
+
+        #[test]
+        fn cache_paths() {
+            let dir = Path::new("cache");
+            assert_eq!(
+                cache_path(dir, "https://tile.example.com/14/8580/5738.png"),
+                dir.join("tile.example.com/14/8580/5738.png")
+            );
+            assert_eq!(
+                cache_path(dir, "http://localhost:8080/tiles/1/2/3.pbf?key=abc"),
+                dir.join("localhost_8080/tiles/1/2/3.pbf_cb13833bf3a2201d")
+            );
+            assert_eq!(cache_path(dir, "not a url"), dir.join("3dfd5357592340f0"));
+        }
+
+        #[test]
+        fn stable_hash() {
+            assert_eq!(fnv1a(""), 0xcbf2_9ce4_8422_2325);
+            assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+        }
     }
 }
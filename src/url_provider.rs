@@ -1,7 +1,12 @@
-use crate::TileId;
+use crate::{Attribution, TileId};
 
 pub trait TileUrlProvider {
     fn url(&self, tile_id: TileId) -> String;
+
+    /// Attribution EMap shows while tiles from this provider are on screen.
+    fn attribution(&self) -> Vec<Attribution> {
+        Vec::new()
+    }
 }
 
 impl<O, F> TileUrlProvider for F
@@ -35,6 +40,14 @@ impl TileUrlProvider for MapBoxTileUrlProvider {
             self.style, tile_id.z, tile_id.x, tile_id.y, self.token
         )
     }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![
+            Attribution::new("© Mapbox").with_url("https://www.mapbox.com/about/maps/"),
+            Attribution::new("© OpenStreetMap contributors")
+                .with_url("https://www.openstreetmap.org/copyright"),
+        ]
+    }
 }
 
 #[derive(Default)]
@@ -47,4 +60,11 @@ impl TileUrlProvider for OsmStandardTileUrlProvider {
             tile_id.z, tile_id.x, tile_id.y
         )
     }
+
+    fn attribution(&self) -> Vec<Attribution> {
+        vec![
+            Attribution::new("© OpenStreetMap contributors")
+                .with_url("https://www.openstreetmap.org/copyright"),
+        ]
+    }
 }
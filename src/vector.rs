@@ -0,0 +1,640 @@
+use std::{
+    collections::HashMap,
+    ops::Deref,
+    sync::{Arc, OnceLock},
+};
+
+use egui::{Align2, Color32, Context, FontId, Mesh, Painter, Pos2, Stroke};
+use geo::Point;
+
+use crate::{
+    DEFAULT_TILE_LOADER, MvtFeature, MvtGeometry, MvtLayer, TileId, TileLoader, TileUrlProvider,
+    VectorTile, label::Label, layer::LayerOptions, tessellation::Triangulation,
+};
+
+pub type FeatureFilter = Arc<dyn Fn(&MvtFeature) -> bool + Send + Sync>;
+
+/// Produces the label text of a feature, `None` if it shouldn't be labelled.
+pub type FeatureText = Arc<dyn Fn(&MvtFeature) -> Option<String> + Send + Sync>;
+
+/// Values [`ZoomValue`] can interpolate between.
+pub trait Interpolate: Clone {
+    fn interpolate(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t as f32
+    }
+}
+
+impl Interpolate for Color32 {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        let [r0, g0, b0, a0] = self.to_array();
+        let [r1, g1, b1, a1] = other.to_array();
+        let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Color32::from_rgba_premultiplied(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1), lerp(a0, a1))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ZoomCurve {
+    Exponential(f64),
+    Step,
+}
+
+/// A paint property that is either constant or depends on the map zoom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoomValue<T> {
+    default: T,
+    stops: Vec<(f64, T)>,
+    curve: ZoomCurve,
+}
+
+impl<T: Interpolate> ZoomValue<T> {
+    pub fn constant(value: T) -> Self {
+        Self {
+            default: value,
+            stops: Vec::new(),
+            curve: ZoomCurve::Step,
+        }
+    }
+
+    /// Interpolates between `(zoom, value)` stops, exponentially with `base` or linearly if it is
+    /// `1.0`. Below the first and above the last stop the value of that stop is used.
+    ///
+    /// Panics if `stops` is empty.
+    pub fn interpolate(base: f64, mut stops: Vec<(f64, T)>) -> Self {
+        assert!(
+            !stops.is_empty(),
+            "interpolated value needs at least one stop"
+        );
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Self {
+            default: stops[0].1.clone(),
+            stops,
+            curve: ZoomCurve::Exponential(base),
+        }
+    }
+
+    /// `default` below the first stop, the value of the closest stop at or below the zoom
+    /// otherwise.
+    pub fn step(default: T, mut stops: Vec<(f64, T)>) -> Self {
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Self {
+            default,
+            stops,
+            curve: ZoomCurve::Step,
+        }
+    }
+
+    pub fn at(&self, zoom: f64) -> T {
+        let Some(i) = self.stops.iter().rposition(|(z, _)| *z <= zoom) else {
+            return self.default.clone();
+        };
+
+        let (z0, v0) = &self.stops[i];
+        match (self.curve, self.stops.get(i + 1)) {
+            (ZoomCurve::Exponential(base), Some((z1, v1))) => {
+                let t = if base == 1.0 {
+                    (zoom - z0) / (z1 - z0)
+                } else {
+                    (base.powf(zoom - z0) - 1.0) / (base.powf(z1 - z0) - 1.0)
+                };
+                v0.interpolate(v1, t)
+            }
+            _ => v0.clone(),
+        }
+    }
+}
+
+impl<T: Interpolate> From<T> for ZoomValue<T> {
+    fn from(value: T) -> Self {
+        Self::constant(value)
+    }
+}
+
+/// A [`Stroke`] whose width and color may depend on the zoom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoomStroke {
+    pub width: ZoomValue<f32>,
+    pub color: ZoomValue<Color32>,
+}
+
+impl ZoomStroke {
+    pub fn new(width: impl Into<ZoomValue<f32>>, color: impl Into<ZoomValue<Color32>>) -> Self {
+        Self {
+            width: width.into(),
+            color: color.into(),
+        }
+    }
+
+    fn at(&self, zoom: f64, opacity: f32) -> Stroke {
+        Stroke::new(
+            self.width.at(zoom),
+            self.color.at(zoom).gamma_multiply(opacity),
+        )
+    }
+}
+
+impl From<Stroke> for ZoomStroke {
+    fn from(stroke: Stroke) -> Self {
+        Self::new(stroke.width, stroke.color)
+    }
+}
+
+#[derive(Clone)]
+pub struct TextPaint {
+    pub text: FeatureText,
+    pub size: ZoomValue<f32>,
+    pub color: ZoomValue<Color32>,
+    pub halo: Option<ZoomStroke>,
+}
+
+#[derive(Clone)]
+pub enum VectorPaint {
+    /// Fills polygons, optionally stroking their rings.
+    Fill {
+        color: ZoomValue<Color32>,
+        outline: Option<ZoomStroke>,
+    },
+    /// Strokes line strings and polygon rings.
+    Line(ZoomStroke),
+    /// Draws a circle with a radius in screen points for every point.
+    Point {
+        radius: ZoomValue<f32>,
+        color: ZoomValue<Color32>,
+    },
+    /// Labels points, the middle of line strings and the center of polygons.
+    Text(TextPaint),
+}
+
+/// Paints the features of one source layer of a vector tile, optionally restricted by a filter.
+#[derive(Clone)]
+pub struct VectorRule {
+    pub(crate) source_layer: String,
+    pub(crate) filter: Option<FeatureFilter>,
+    pub(crate) min_zoom: f64,
+    pub(crate) max_zoom: f64,
+    pub(crate) opacity: ZoomValue<f32>,
+    pub(crate) paint: VectorPaint,
+}
+
+impl VectorRule {
+    pub fn new(source_layer: impl Into<String>, paint: VectorPaint) -> Self {
+        Self {
+            source_layer: source_layer.into(),
+            filter: None,
+            min_zoom: 0.0,
+            max_zoom: f64::INFINITY,
+            opacity: ZoomValue::constant(1.0),
+            paint,
+        }
+    }
+
+    pub fn fill(source_layer: impl Into<String>, color: impl Into<ZoomValue<Color32>>) -> Self {
+        Self::new(
+            source_layer,
+            VectorPaint::Fill {
+                color: color.into(),
+                outline: None,
+            },
+        )
+    }
+
+    pub fn line(source_layer: impl Into<String>, stroke: impl Into<ZoomStroke>) -> Self {
+        Self::new(source_layer, VectorPaint::Line(stroke.into()))
+    }
+
+    pub fn point(
+        source_layer: impl Into<String>,
+        radius: impl Into<ZoomValue<f32>>,
+        color: impl Into<ZoomValue<Color32>>,
+    ) -> Self {
+        Self::new(
+            source_layer,
+            VectorPaint::Point {
+                radius: radius.into(),
+                color: color.into(),
+            },
+        )
+    }
+
+    /// Labels features with `template`, in which `{property}` is replaced by the value of that
+    /// property, or by nothing if the feature doesn't have it. Features whose label ends up
+    /// empty aren't labelled.
+    pub fn text(
+        source_layer: impl Into<String>,
+        template: &str,
+        size: impl Into<ZoomValue<f32>>,
+        color: impl Into<ZoomValue<Color32>>,
+    ) -> Self {
+        let template = template.to_string();
+        Self::new(
+            source_layer,
+            VectorPaint::Text(TextPaint {
+                text: Arc::new(move |f| format_template(&template, f)),
+                size: size.into(),
+                color: color.into(),
+                halo: None,
+            }),
+        )
+    }
+
+    /// Strokes the rings of filled polygons. Has no effect on other rules.
+    pub fn outline(mut self, stroke: impl Into<ZoomStroke>) -> Self {
+        if let VectorPaint::Fill { outline, .. } = &mut self.paint {
+            *outline = Some(stroke.into());
+        }
+        self
+    }
+
+    /// Draws a halo of `stroke.width` around labels. Has no effect on other rules.
+    pub fn halo(mut self, stroke: impl Into<ZoomStroke>) -> Self {
+        if let VectorPaint::Text(text) = &mut self.paint {
+            text.halo = Some(stroke.into());
+        }
+        self
+    }
+
+    /// Only paint features for which `filter` returns `true`.
+    pub fn filter(mut self, filter: impl Fn(&MvtFeature) -> bool + Send + Sync + 'static) -> Self {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Only paint while the map zoom is within `min_zoom..max_zoom`.
+    pub fn zoom_range(mut self, min_zoom: f64, max_zoom: f64) -> Self {
+        self.min_zoom = min_zoom;
+        self.max_zoom = max_zoom;
+        self
+    }
+
+    /// Opacity of the rule, multiplied with the opacity of the layer.
+    pub fn opacity(mut self, opacity: impl Into<ZoomValue<f32>>) -> Self {
+        self.opacity = opacity.into();
+        self
+    }
+
+    fn applies_at(&self, zoom: f64) -> bool {
+        (self.min_zoom..self.max_zoom).contains(&zoom)
+    }
+
+    fn applies_to(&self, feature: &MvtFeature) -> bool {
+        self.filter.as_ref().is_none_or(|f| f(feature))
+    }
+}
+
+/// Replaces `{property}` tokens in `template` like MapLibre does, with an empty string for
+/// properties the feature doesn't have. `None` if nothing but whitespace is left.
+pub(crate) fn format_template(template: &str, feature: &MvtFeature) -> Option<String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        if let Some(value) = feature.properties.get(&rest[start + 1..end]) {
+            out.push_str(&value.to_string());
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+
+    let out = out.trim();
+    (!out.is_empty()).then(|| out.to_string())
+}
+
+/// Rules applied in order, so features matched by later rules are painted on top.
+///
+/// ```
+/// use egui::{Color32, Stroke};
+/// use emap::{VectorRule, VectorStyle};
+///
+/// let style = VectorStyle::new()
+///     .background(Color32::from_gray(240))
+///     .rule(VectorRule::fill("water", Color32::LIGHT_BLUE))
+///     .rule(
+///         VectorRule::line("transportation", Stroke::new(2.0, Color32::WHITE))
+///             .filter(|f| f.properties.get("class").and_then(|c| c.as_str()) == Some("primary")),
+///     )
+///     .rule(VectorRule::text("place", "{name}", 14.0, Color32::BLACK));
+/// ```
+#[derive(Clone, Default)]
+pub struct VectorStyle {
+    pub(crate) background: Option<ZoomValue<Color32>>,
+    pub(crate) rules: Vec<VectorRule>,
+}
+
+impl VectorStyle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fills the whole map below the tiles of the layer.
+    pub fn background(mut self, color: impl Into<ZoomValue<Color32>>) -> Self {
+        self.background = Some(color.into());
+        self
+    }
+
+    pub fn rule(mut self, rule: VectorRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+}
+
+/// A layer of [Mapbox Vector Tiles](https://docs.mapbox.com/data/tilesets/guides/vector-tiles-standards/),
+/// fetched through the [`TileLoader::tile_data`] of its loader and painted with a [`VectorStyle`].
+pub struct VectorTileLayer<'t> {
+    pub(crate) provider: &'t dyn TileUrlProvider,
+    pub(crate) loader: Option<&'t dyn TileLoader>,
+    pub(crate) style: &'t VectorStyle,
+
+    pub(crate) options: LayerOptions,
+}
+
+impl<'t> VectorTileLayer<'t> {
+    pub fn new(provider: &'t dyn TileUrlProvider, style: &'t VectorStyle) -> Self {
+        Self {
+            provider,
+            loader: None,
+            style,
+
+            options: LayerOptions {
+                max_data_zoom: 14,
+                ..LayerOptions::default()
+            },
+        }
+    }
+
+    pub fn tile_loader(mut self, loader: &'t dyn TileLoader) -> Self {
+        self.loader = Some(loader);
+        self
+    }
+
+    /// Opacity between `0.0` (invisible) and `1.0` (opaque).
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.options.set_opacity(opacity);
+        self
+    }
+
+    /// Only draw the layer while the map zoom is within `min_zoom..=max_zoom`.
+    pub fn zoom_range(mut self, min_zoom: u8, max_zoom: u8) -> Self {
+        self.options.set_zoom_range(min_zoom, max_zoom);
+        self
+    }
+
+    /// The highest zoom level the tile source has data for, tiles of this level are scaled up
+    /// when zooming in further. Defaults to 14, like most OpenMapTiles based sources.
+    pub fn max_data_zoom(mut self, zoom: u8) -> Self {
+        self.options.max_data_zoom = zoom;
+        self
+    }
+
+    /// The size of the tiles in pixels if it differs from the tile size of the map, e.g. `512`
+    /// for sources whose tiles are meant to cover 512 screen points.
+    pub fn tile_size(mut self, size: u32) -> Self {
+        self.options.tile_size = Some(size);
+        self
+    }
+
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.options.visible = visible;
+        self
+    }
+}
+
+/// A vector tile that is decoded and triangulated in the background, empty until it is done.
+pub(crate) type LoadingTile = Arc<OnceLock<Arc<PreparedTile>>>;
+
+/// A tile waiting to be prepared, with the context to repaint once it is.
+struct DecodeJob {
+    data: Arc<[u8]>,
+    result: LoadingTile,
+    ctx: Context,
+}
+
+impl DecodeJob {
+    fn run(self) {
+        // a tile that fails to decode is kept as an empty one, so it isn't requested over and
+        // over, the same goes for a tile the decoder or the triangulation panics on
+        let tile = std::panic::catch_unwind(|| {
+            PreparedTile::new(VectorTile::decode(&self.data).unwrap_or_default())
+        })
+        .unwrap_or_else(|_| PreparedTile::new(VectorTile::default()));
+        let _ = self.result.set(Arc::new(tile));
+        self.ctx.request_repaint();
+    }
+}
+
+/// The thread all vector tiles are prepared on, in the order they were requested.
+#[cfg(not(target_arch = "wasm32"))]
+static DECODER: std::sync::LazyLock<std::sync::mpsc::Sender<DecodeJob>> =
+    std::sync::LazyLock::new(|| {
+        let (sender, receiver) = std::sync::mpsc::channel::<DecodeJob>();
+        // without the thread the receiver is dropped and sending fails
+        let _ = std::thread::Builder::new()
+            .name("emap vector tiles".to_string())
+            .spawn(move || {
+                for job in receiver {
+                    // skip tiles that were unloaded while they were waiting
+                    if Arc::strong_count(&job.result) > 1 {
+                        job.run();
+                    }
+                }
+            });
+        sender
+    });
+
+/// A decoded vector tile with its polygons already triangulated.
+pub(crate) struct PreparedTile {
+    layers: Vec<PreparedLayer>,
+}
+
+struct PreparedLayer {
+    layer: MvtLayer,
+    /// triangulation of every polygon, per feature
+    fills: Vec<Vec<Triangulation>>,
+}
+
+impl PreparedTile {
+    /// Starts preparing the tile at `url` once its loader has the data. Tiles are prepared one
+    /// after another on a background thread so large tiles don't stall the frame, except on the
+    /// web, where they are prepared right away.
+    pub fn load(
+        layer: &VectorTileLayer<'_>,
+        url: String,
+        tile: &TileId,
+        ctx: &Context,
+    ) -> Option<LoadingTile> {
+        let loader: &dyn TileLoader = layer.loader.unwrap_or_else(|| DEFAULT_TILE_LOADER.deref());
+
+        let data = loader.tile_data(url, tile, ctx.clone())?;
+
+        let loading = LoadingTile::default();
+        let job = DecodeJob {
+            data,
+            result: loading.clone(),
+            ctx: ctx.clone(),
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(std::sync::mpsc::SendError(job)) = DECODER.send(job) {
+            // the decoder thread could not be started
+            job.run();
+        }
+        #[cfg(target_arch = "wasm32")]
+        job.run();
+
+        Some(loading)
+    }
+
+    fn new(tile: VectorTile) -> Self {
+        let layers = tile
+            .layers
+            .into_iter()
+            .map(|layer| {
+                let fills = layer
+                    .features
+                    .iter()
+                    .map(|f| match &f.geometry {
+                        MvtGeometry::Polygons(polygons) => polygons
+                            .iter()
+                            .map(|rings| Triangulation::from_rings(rings))
+                            .collect(),
+                        _ => Vec::new(),
+                    })
+                    .collect();
+                PreparedLayer { layer, fills }
+            })
+            .collect();
+
+        Self { layers }
+    }
+
+    /// Paints the tile with `style`, `project` maps tile relative coordinates to the screen.
+    /// Labels are collected into `labels` instead, so they can be drawn on top of all tiles.
+    pub fn paint(
+        &self,
+        painter: &Painter,
+        style: &VectorStyle,
+        zoom: f64,
+        opacity: f32,
+        project: impl Fn(Point<f64>) -> Pos2,
+        labels: &mut Vec<Label>,
+    ) {
+        let layers = self
+            .layers
+            .iter()
+            .map(|l| (l.layer.name.as_str(), l))
+            .collect::<HashMap<_, _>>();
+
+        for rule in &style.rules {
+            if !rule.applies_at(zoom) {
+                continue;
+            }
+            let Some(layer) = layers.get(rule.source_layer.as_str()) else {
+                continue;
+            };
+
+            let opacity = opacity * rule.opacity.at(zoom).clamp(0.0, 1.0);
+            if opacity <= 0.0 {
+                continue;
+            }
+
+            let mut mesh = Mesh::default();
+
+            for (feature, fills) in layer.layer.features.iter().zip(&layer.fills) {
+                if !rule.applies_to(feature) {
+                    continue;
+                }
+
+                match (&rule.paint, &feature.geometry) {
+                    (VectorPaint::Fill { color, outline }, MvtGeometry::Polygons(polygons)) => {
+                        let color = color.at(zoom).gamma_multiply(opacity);
+                        for fill in fills {
+                            fill.add_to_mesh(&mut mesh, color, &project);
+                        }
+
+                        if let Some(stroke) = outline {
+                            let stroke = stroke.at(zoom, opacity);
+                            for ring in polygons.iter().flatten() {
+                                painter.line(ring.iter().map(|p| project(*p)).collect(), stroke);
+                            }
+                        }
+                    }
+                    (VectorPaint::Line(stroke), MvtGeometry::LineStrings(lines)) => {
+                        let stroke = stroke.at(zoom, opacity);
+                        for line in lines {
+                            painter.line(line.iter().map(|p| project(*p)).collect(), stroke);
+                        }
+                    }
+                    (VectorPaint::Line(stroke), MvtGeometry::Polygons(polygons)) => {
+                        let stroke = stroke.at(zoom, opacity);
+                        for ring in polygons.iter().flatten() {
+                            painter.line(ring.iter().map(|p| project(*p)).collect(), stroke);
+                        }
+                    }
+                    (VectorPaint::Point { radius, color }, MvtGeometry::Points(points)) => {
+                        let radius = radius.at(zoom);
+                        let color = color.at(zoom).gamma_multiply(opacity);
+                        for p in points {
+                            painter.circle_filled(project(*p), radius, color);
+                        }
+                    }
+                    (VectorPaint::Text(paint), geometry) => {
+                        let Some(text) = (paint.text)(feature) else {
+                            continue;
+                        };
+
+                        // features in the tile buffer are repeated in the neighbouring tile, only
+                        // label them in the tile they belong to
+                        let Some(anchor) = label_anchor(geometry).filter(|p| in_tile(*p)) else {
+                            continue;
+                        };
+
+                        labels.push(Label {
+                            pos: project(anchor),
+                            text,
+                            font: FontId::proportional(paint.size.at(zoom)),
+                            color: paint.color.at(zoom).gamma_multiply(opacity),
+                            halo: paint.halo.as_ref().map(|h| h.at(zoom, opacity)),
+                            anchor: Align2::CENTER_CENTER,
+                            priority: 0,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+
+            if !mesh.is_empty() {
+                painter.add(mesh);
+            }
+        }
+    }
+}
+
+fn in_tile(p: Point<f64>) -> bool {
+    (0.0..1.0).contains(&p.x()) && (0.0..1.0).contains(&p.y())
+}
+
+fn label_anchor(geometry: &MvtGeometry) -> Option<Point<f64>> {
+    match geometry {
+        MvtGeometry::Points(points) => points.first().copied(),
+        MvtGeometry::LineStrings(lines) => {
+            let line = lines.iter().max_by_key(|l| l.len())?;
+            line.get(line.len() / 2).copied()
+        }
+        MvtGeometry::Polygons(polygons) => {
+            let exterior = polygons.first()?.first()?;
+            let n = exterior.len() as f64;
+            let sum = exterior
+                .iter()
+                .fold(Point::new(0.0, 0.0), |acc, p| acc + *p);
+            (n > 0.0).then(|| sum / n)
+        }
+    }
+}
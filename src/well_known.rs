@@ -0,0 +1,556 @@
+//! Reading of geometries in the well known text and binary formats, as returned by spatial
+//! databases like PostGIS and SpatiaLite, for drawing them with [`crate::EMap::geometry`].
+//!
+//! Coordinates are expected in WGS 84 longitude/latitude (EPSG:4326). Extended WKT and WKB
+//! carrying the SRID of web mercator (EPSG:3857) are converted to it, other SRIDs are rejected.
+//! Z and M values are dropped.
+
+use geo::{Coord, Geometry, MapCoords};
+#[cfg(feature = "wkb")]
+use geo::{
+    GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon,
+};
+
+/// How deep geometry collections may be nested, deeper input is rejected instead of risking a
+/// stack overflow.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug)]
+pub enum WellKnownError {
+    #[cfg(feature = "wkt")]
+    Wkt(wkt::geo_types_from_wkt::Error),
+    UnexpectedEof,
+    InvalidByteOrder(u8),
+    UnsupportedGeometryType(u32),
+    UnsupportedSrid(i32),
+    /// The `SRID=` prefix of extended WKT isn't followed by a number.
+    InvalidSrid(String),
+    InvalidHex,
+    /// Geometry collections nested deeper than 64 levels.
+    TooDeeplyNested,
+}
+
+impl std::fmt::Display for WellKnownError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "wkt")]
+            WellKnownError::Wkt(e) => write!(f, "invalid wkt: {e}"),
+            WellKnownError::UnexpectedEof => write!(f, "unexpected end of wkb data"),
+            WellKnownError::InvalidByteOrder(b) => write!(f, "invalid wkb byte order {b}"),
+            WellKnownError::UnsupportedGeometryType(t) => {
+                write!(f, "unsupported wkb geometry type {t}")
+            }
+            WellKnownError::UnsupportedSrid(srid) => write!(f, "unsupported srid {srid}"),
+            WellKnownError::InvalidSrid(srid) => write!(f, "invalid srid {srid:?}"),
+            WellKnownError::InvalidHex => write!(f, "invalid hex encoded wkb"),
+            WellKnownError::TooDeeplyNested => write!(f, "geometry collections nested too deeply"),
+        }
+    }
+}
+
+impl std::error::Error for WellKnownError {}
+
+#[cfg(feature = "wkt")]
+impl From<wkt::geo_types_from_wkt::Error> for WellKnownError {
+    fn from(e: wkt::geo_types_from_wkt::Error) -> Self {
+        WellKnownError::Wkt(e)
+    }
+}
+
+/// Parses WKT such as `POINT(8.5 47.4)`, or extended WKT with a `SRID=3857;` prefix.
+#[cfg(feature = "wkt")]
+pub fn geometry_from_wkt(text: &str) -> Result<Geometry<f64>, WellKnownError> {
+    use wkt::TryFromWkt;
+
+    let text = text.trim();
+    let (srid, text) = match text.split_once(';') {
+        Some((prefix, rest)) if prefix.trim().to_ascii_uppercase().starts_with("SRID=") => {
+            let srid = prefix.trim()[5..].trim();
+            let srid = srid
+                .parse()
+                .map_err(|_| WellKnownError::InvalidSrid(srid.to_string()))?;
+            (Some(srid), rest)
+        }
+        _ => (None, text),
+    };
+
+    // the parser recurses into every parenthesis, and a multi polygon already nests its
+    // coordinates three deep
+    let mut depth = 0usize;
+    for c in text.bytes() {
+        match c {
+            b'(' => depth += 1,
+            b')' => depth = depth.saturating_sub(1),
+            _ => continue,
+        }
+        if depth > MAX_DEPTH + 3 {
+            return Err(WellKnownError::TooDeeplyNested);
+        }
+    }
+
+    let geometry = Geometry::try_from_wkt_str(text)?;
+    to_lon_lat(geometry, srid)
+}
+
+/// Parses WKB or PostGIS EWKB in either byte order.
+#[cfg(feature = "wkb")]
+pub fn geometry_from_wkb(bytes: &[u8]) -> Result<Geometry<f64>, WellKnownError> {
+    let mut reader = Reader {
+        data: bytes,
+        le: false,
+        depth: 0,
+    };
+    let (geometry, srid) = reader.geometry()?;
+    to_lon_lat(geometry, srid)
+}
+
+/// Parses hex encoded WKB or EWKB, the way PostGIS returns geometry columns as text.
+///
+/// ```no_run
+/// # fn rows() -> Vec<String> { Vec::new() }
+/// use emap::{EMap, GeometryStyle, geometry_from_hex_wkb};
+///
+/// let mut map = EMap::new("map");
+/// for hex in rows() {
+///     map = map.geometry(geometry_from_hex_wkb(&hex).unwrap(), GeometryStyle::new());
+/// }
+/// ```
+#[cfg(feature = "wkb")]
+pub fn geometry_from_hex_wkb(hex: &str) -> Result<Geometry<f64>, WellKnownError> {
+    let hex = hex.trim();
+    let hex = hex.strip_prefix("\\x").unwrap_or(hex);
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                .ok_or(WellKnownError::InvalidHex)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    geometry_from_wkb(&bytes)
+}
+
+fn to_lon_lat(geometry: Geometry<f64>, srid: Option<i32>) -> Result<Geometry<f64>, WellKnownError> {
+    match srid {
+        None | Some(0) | Some(4326) => Ok(geometry),
+        Some(3857) | Some(900913) => Ok(geometry.map_coords(|c| {
+            const R: f64 = 6_378_137.0;
+            Coord {
+                x: (c.x / R).to_degrees(),
+                y: (c.y / R).sinh().atan().to_degrees(),
+            }
+        })),
+        Some(srid) => Err(WellKnownError::UnsupportedSrid(srid)),
+    }
+}
+
+#[cfg(feature = "wkb")]
+struct Reader<'a> {
+    data: &'a [u8],
+    le: bool,
+    /// How many geometries the one being read is nested in.
+    depth: usize,
+}
+
+#[cfg(feature = "wkb")]
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], WellKnownError> {
+        let (bytes, rest) = self
+            .data
+            .split_first_chunk::<N>()
+            .ok_or(WellKnownError::UnexpectedEof)?;
+        self.data = rest;
+        Ok(*bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, WellKnownError> {
+        let b = self.take()?;
+        Ok(if self.le {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    fn f64(&mut self) -> Result<f64, WellKnownError> {
+        let b = self.take()?;
+        Ok(if self.le {
+            f64::from_le_bytes(b)
+        } else {
+            f64::from_be_bytes(b)
+        })
+    }
+
+    /// A count followed by that many items, which take at least `min_size` bytes each.
+    fn list<T>(
+        &mut self,
+        min_size: usize,
+        mut item: impl FnMut(&mut Self) -> Result<T, WellKnownError>,
+    ) -> Result<Vec<T>, WellKnownError> {
+        let n = self.u32()? as usize;
+        // don't trust the count for the allocation
+        if n.saturating_mul(min_size) > self.data.len() {
+            return Err(WellKnownError::UnexpectedEof);
+        }
+        (0..n).map(|_| item(self)).collect()
+    }
+
+    /// Reads a geometry with its header, returning the SRID of EWKB.
+    fn geometry(&mut self) -> Result<(Geometry<f64>, Option<i32>), WellKnownError> {
+        if self.depth > MAX_DEPTH {
+            return Err(WellKnownError::TooDeeplyNested);
+        }
+        self.depth += 1;
+        let geometry = self.geometry_body();
+        self.depth -= 1;
+        geometry
+    }
+
+    fn geometry_body(&mut self) -> Result<(Geometry<f64>, Option<i32>), WellKnownError> {
+        self.le = match self.take::<1>()?[0] {
+            0 => false,
+            1 => true,
+            b => return Err(WellKnownError::InvalidByteOrder(b)),
+        };
+
+        let raw = self.u32()?;
+        // EWKB flags the dimensions and presence of a SRID in the high bits, ISO WKB adds
+        // 1000 for Z, 2000 for M and 3000 for ZM to the type
+        let ewkb_z = raw & 0x8000_0000 != 0;
+        let ewkb_m = raw & 0x4000_0000 != 0;
+        let has_srid = raw & 0x2000_0000 != 0;
+        let iso = raw & 0x0fff_ffff;
+        let (kind, iso_dims) = (iso % 1000, iso / 1000);
+        let extra = match iso_dims {
+            0 => ewkb_z as usize + ewkb_m as usize,
+            1 | 2 => 1,
+            3 => 2,
+            _ => return Err(WellKnownError::UnsupportedGeometryType(raw)),
+        };
+
+        let srid = if has_srid {
+            Some(self.u32()? as i32)
+        } else {
+            None
+        };
+
+        let coord_size = 8 * (2 + extra);
+        let coord = |r: &mut Self| -> Result<Coord<f64>, WellKnownError> {
+            let c = Coord {
+                x: r.f64()?,
+                y: r.f64()?,
+            };
+            for _ in 0..extra {
+                r.f64()?;
+            }
+            Ok(c)
+        };
+        let line = |r: &mut Self| r.list(coord_size, coord).map(LineString::new);
+        let polygon = |r: &mut Self| -> Result<Polygon<f64>, WellKnownError> {
+            let mut rings = r.list(4, line)?.into_iter();
+            Ok(match rings.next() {
+                Some(exterior) => Polygon::new(exterior, rings.collect()),
+                None => Polygon::new(LineString::new(Vec::new()), Vec::new()),
+            })
+        };
+        let member = |r: &mut Self| r.geometry().map(|(g, _)| g);
+
+        let geometry = match kind {
+            1 => {
+                let c = coord(self)?;
+                // an empty point is written as NaN coordinates
+                if c.x.is_nan() && c.y.is_nan() {
+                    Geometry::GeometryCollection(GeometryCollection::default())
+                } else {
+                    Point::from(c).into()
+                }
+            }
+            2 => line(self)?.into(),
+            3 => polygon(self)?.into(),
+            4 => {
+                let points = self.list(5 + coord_size, member)?;
+                MultiPoint::new(
+                    points
+                        .into_iter()
+                        .filter_map(|g| g.try_into().ok())
+                        .collect(),
+                )
+                .into()
+            }
+            5 => {
+                let lines = self.list(9, member)?;
+                MultiLineString::new(
+                    lines
+                        .into_iter()
+                        .filter_map(|g| g.try_into().ok())
+                        .collect(),
+                )
+                .into()
+            }
+            6 => {
+                let polygons = self.list(9, member)?;
+                MultiPolygon::new(
+                    polygons
+                        .into_iter()
+                        .filter_map(|g| g.try_into().ok())
+                        .collect(),
+                )
+                .into()
+            }
+            7 => Geometry::GeometryCollection(GeometryCollection::new_from(self.list(5, member)?)),
+            _ => return Err(WellKnownError::UnsupportedGeometryType(raw)),
+        };
+
+        Ok((geometry, srid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::{Geometry, GeometryCollection, LineString, Point, point};
+
+    use super::*;
+
+    fn assert_close(geometry: &Geometry<f64>, expected: Point<f64>) {
+        let Geometry::Point(p) = geometry else {
+            panic!("expected a point, got {geometry:?}");
+        };
+        assert!((p.x() - expected.x()).abs() < 1e-9, "{p:?}");
+        assert!((p.y() - expected.y()).abs() < 1e-9, "{p:?}");
+    }
+
+    #[cfg(feature = "wkb")]
+    fn wkb_header(buf: &mut Vec<u8>, kind: u32, srid: Option<i32>) {
+        buf.push(1);
+        let kind = if srid.is_some() {
+            kind | 0x2000_0000
+        } else {
+            kind
+        };
+        buf.extend(kind.to_le_bytes());
+        if let Some(srid) = srid {
+            buf.extend(srid.to_le_bytes());
+        }
+    }
+
+    #[cfg(feature = "wkb")]
+    fn wkb_point(x: f64, y: f64, srid: Option<i32>) -> Vec<u8> {
+        let mut buf = Vec::new();
+        wkb_header(&mut buf, 1, srid);
+        buf.extend(x.to_le_bytes());
+        buf.extend(y.to_le_bytes());
+        buf
+    }
+
+    /// `depth` geometry collections nested in each other around a point.
+    #[cfg(feature = "wkb")]
+    fn nested_wkb(depth: usize) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for _ in 0..depth {
+            wkb_header(&mut buf, 7, None);
+            buf.extend(1u32.to_le_bytes());
+        }
+        buf.extend(wkb_point(1.0, 2.0, None));
+        buf
+    }
+
+    #[cfg(feature = "wkb")]
+    #[test]
+    fn wkb() {
+        assert_close(
+            &geometry_from_wkb(&wkb_point(8.5, 47.4, None)).unwrap(),
+            point!(x: 8.5, y: 47.4),
+        );
+
+        // big endian line string
+        let mut line = vec![0];
+        line.extend(2u32.to_be_bytes());
+        line.extend(2u32.to_be_bytes());
+        for v in [1.0f64, 2.0, 3.0, 4.0] {
+            line.extend(v.to_be_bytes());
+        }
+        assert_eq!(
+            geometry_from_wkb(&line).unwrap(),
+            Geometry::LineString(LineString::from(vec![(1.0, 2.0), (3.0, 4.0)]))
+        );
+
+        assert_close(
+            &geometry_from_hex_wkb("0101000000000000000000F03F0000000000000040").unwrap(),
+            point!(x: 1.0, y: 2.0),
+        );
+        assert!(matches!(
+            geometry_from_hex_wkb("0101zz"),
+            Err(WellKnownError::InvalidHex)
+        ));
+        assert!(matches!(
+            geometry_from_wkb(&[2, 1, 0, 0, 0]),
+            Err(WellKnownError::InvalidByteOrder(2))
+        ));
+    }
+
+    #[cfg(feature = "wkb")]
+    #[test]
+    fn ewkb_with_srid() {
+        assert_close(
+            &geometry_from_wkb(&wkb_point(8.5, 47.4, Some(4326))).unwrap(),
+            point!(x: 8.5, y: 47.4),
+        );
+
+        let mercator = wkb_point(946_215.672, 6_007_610.414, Some(3857));
+        let geometry = geometry_from_wkb(&mercator).unwrap();
+        let Geometry::Point(p) = geometry else {
+            panic!("expected a point, got {geometry:?}");
+        };
+        assert!(
+            (p.x() - 8.5).abs() < 1e-3 && (p.y() - 47.4).abs() < 1e-3,
+            "{p:?}"
+        );
+
+        assert!(matches!(
+            geometry_from_wkb(&wkb_point(2_600_000.0, 1_200_000.0, Some(2056))),
+            Err(WellKnownError::UnsupportedSrid(2056))
+        ));
+    }
+
+    #[cfg(feature = "wkb")]
+    #[test]
+    fn empty_wkb() {
+        assert_eq!(
+            geometry_from_wkb(&wkb_point(f64::NAN, f64::NAN, None)).unwrap(),
+            Geometry::GeometryCollection(GeometryCollection::default())
+        );
+
+        let mut collection = Vec::new();
+        wkb_header(&mut collection, 7, None);
+        collection.extend(0u32.to_le_bytes());
+        assert_eq!(
+            geometry_from_wkb(&collection).unwrap(),
+            Geometry::GeometryCollection(GeometryCollection::default())
+        );
+
+        assert!(matches!(
+            geometry_from_wkb(&[]),
+            Err(WellKnownError::UnexpectedEof)
+        ));
+    }
+
+    #[cfg(feature = "wkb")]
+    #[test]
+    fn truncated_wkb() {
+        let point = wkb_point(8.5, 47.4, Some(3857));
+        for len in 0..point.len() {
+            assert!(
+                matches!(
+                    geometry_from_wkb(&point[..len]),
+                    Err(WellKnownError::UnexpectedEof)
+                ),
+                "truncated to {len} bytes"
+            );
+        }
+
+        // a line string claiming more points than there are bytes for
+        let mut line = Vec::new();
+        wkb_header(&mut line, 2, None);
+        line.extend(u32::MAX.to_le_bytes());
+        assert!(matches!(
+            geometry_from_wkb(&line),
+            Err(WellKnownError::UnexpectedEof)
+        ));
+    }
+
+    #[cfg(feature = "wkb")]
+    #[test]
+    fn deeply_nested_wkb() {
+        assert!(geometry_from_wkb(&nested_wkb(MAX_DEPTH)).is_ok());
+        assert!(matches!(
+            geometry_from_wkb(&nested_wkb(MAX_DEPTH + 1)),
+            Err(WellKnownError::TooDeeplyNested)
+        ));
+        assert!(matches!(
+            geometry_from_wkb(&nested_wkb(100_000)),
+            Err(WellKnownError::TooDeeplyNested)
+        ));
+    }
+
+    #[cfg(feature = "wkt")]
+    #[test]
+    fn wkt() {
+        assert_close(
+            &geometry_from_wkt("POINT(8.5 47.4)").unwrap(),
+            point!(x: 8.5, y: 47.4),
+        );
+        assert_close(
+            &geometry_from_wkt("SRID=4326;POINT(8.5 47.4)").unwrap(),
+            point!(x: 8.5, y: 47.4),
+        );
+        assert!(matches!(
+            geometry_from_wkt("MULTIPOLYGON(((0 0,1 0,1 1,0 0)))").unwrap(),
+            Geometry::MultiPolygon(_)
+        ));
+    }
+
+    #[cfg(feature = "wkt")]
+    #[test]
+    fn wkt_with_srid() {
+        let geometry = geometry_from_wkt("SRID=3857;POINT(946215.672 6007610.414)").unwrap();
+        let Geometry::Point(p) = geometry else {
+            panic!("expected a point, got {geometry:?}");
+        };
+        assert!(
+            (p.x() - 8.5).abs() < 1e-3 && (p.y() - 47.4).abs() < 1e-3,
+            "{p:?}"
+        );
+
+        assert!(matches!(
+            geometry_from_wkt(" srid=3857 ; POINT(0 0)"),
+            Ok(Geometry::Point(_))
+        ));
+        assert!(matches!(
+            geometry_from_wkt("SRID=2056;POINT(2600000 1200000)"),
+            Err(WellKnownError::UnsupportedSrid(2056))
+        ));
+        assert!(matches!(
+            geometry_from_wkt("SRID=web;POINT(0 0)"),
+            Err(WellKnownError::InvalidSrid(srid)) if srid == "web"
+        ));
+    }
+
+    #[cfg(feature = "wkt")]
+    #[test]
+    fn empty_wkt() {
+        assert_eq!(
+            geometry_from_wkt("GEOMETRYCOLLECTION EMPTY").unwrap(),
+            Geometry::GeometryCollection(GeometryCollection::default())
+        );
+        assert_eq!(
+            geometry_from_wkt("LINESTRING EMPTY").unwrap(),
+            Geometry::LineString(LineString::new(Vec::new()))
+        );
+        assert!(matches!(geometry_from_wkt(""), Err(WellKnownError::Wkt(_))));
+    }
+
+    #[cfg(feature = "wkt")]
+    #[test]
+    fn truncated_wkt() {
+        assert!(matches!(
+            geometry_from_wkt("POINT(8.5 47.4"),
+            Err(WellKnownError::Wkt(_))
+        ));
+        assert!(matches!(
+            geometry_from_wkt("LINESTRING(0 0, 1"),
+            Err(WellKnownError::Wkt(_))
+        ));
+    }
+
+    #[cfg(feature = "wkt")]
+    #[test]
+    fn deeply_nested_wkt() {
+        let nested =
+            |depth: usize| "GEOMETRYCOLLECTION(".repeat(depth) + "POINT(1 2)" + &")".repeat(depth);
+        assert!(geometry_from_wkt(&nested(MAX_DEPTH)).is_ok());
+        assert!(matches!(
+            geometry_from_wkt(&nested(100_000)),
+            Err(WellKnownError::TooDeeplyNested)
+        ));
+    }
+}
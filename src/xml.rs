@@ -0,0 +1,20 @@
+//! Helpers for walking the `roxmltree` documents of the GPX and KML parsers, matching elements
+//! by their local name regardless of namespace.
+
+use roxmltree::Node;
+
+/// The child elements of `node` named `name`.
+pub(crate) fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(move |n| n.tag_name().name() == name)
+}
+
+/// The trimmed text of the first child element of `node` named `name`.
+pub(crate) fn child_text(node: Node, name: &str) -> Option<String> {
+    node.children()
+        .find(|n| n.tag_name().name() == name)
+        .and_then(|n| n.text())
+        .map(|t| t.trim().to_string())
+}